@@ -1,22 +1,53 @@
 use crate::config::Config;
-use crate::state::{AppState, AppStatus, AudioCommand};
+use crate::state::{AppState, AppStatus, AudioCommand, AudioStatus};
 use crate::uploader;
 use crate::notifications;
+use crate::vad;
+use crate::config::DevicePreference;
+use crate::encoder::SegmentCodec;
+use crate::manifest;
 use anyhow;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::Device;
 use crossbeam_channel::Receiver;
-use hound;
 use log::{info, warn};
+use serde::Serialize;
+use std::collections::VecDeque;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
-    Arc,
+    Arc, Mutex,
 };
 use std::thread;
 use tauri::AppHandle;
 
 // Removed mod mac_sc; declaration from here as it should be in main.rs/lib.rs
 
+/// RMS level (0.0-1.0) of a mono sample buffer, used to drive the input/output
+/// level meters surfaced over `AudioStatus::LevelUpdate`.
+fn calculate_rms(data: &[f32]) -> f32 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    let sum_squares: f32 = data.iter().map(|s| s * s).sum();
+    (sum_squares / data.len() as f32).sqrt()
+}
+
+/// How long a device supervisor waits before its first reconnect attempt, and the
+/// cap later attempts back off toward; the delay doubles each attempt in between.
+const DEVICE_RETRY_INITIAL_MS: u64 = 200;
+const DEVICE_RETRY_MAX_MS: u64 = 3200;
+/// Reconnect attempts to exhaust before a supervisor gives up on a dropped device
+/// for the rest of the segment (mic falls back to the Virtual Silence Generator;
+/// sys just stops feeding `run_mixing_loop`, which already mixes in silence when
+/// the sys channel is empty).
+const DEVICE_RETRY_MAX_ATTEMPTS: u32 = 8;
+
+/// How many mixed chunks `run_mixing_loop`'s producer is allowed to get ahead of
+/// `run_segment_encoder_task`'s consumer before a push blocks. At the mixing loop's
+/// ~10-30ms chunk cadence this is a few seconds of slack — enough to ride out a slow
+/// disk write without the real-time mixing thread itself touching disk.
+const SEGMENT_RING_BUFFER_CAPACITY: usize = 128;
+
 fn find_input_device(host: &cpal::Host, config: &Config) -> Option<Device> {
     if let Some(ref name) = config.input_device_name {
         if let Ok(devices) = host.input_devices() {
@@ -57,6 +88,133 @@ fn find_output_device(host: &cpal::Host, config: &Config) -> Option<Device> {
     host.default_output_device()
 }
 
+/// One sample-rate/channel/format combination a device reported support for, from
+/// `supported_input_configs()`/`supported_output_configs()`.
+#[derive(Serialize, Clone, Debug)]
+pub struct AudioDeviceConfigRange {
+    pub min_sample_rate: u32,
+    pub max_sample_rate: u32,
+    pub channels: u16,
+    pub sample_format: String,
+}
+
+/// One input or output device, as reported by `enumerate_audio_devices`.
+#[derive(Serialize, Clone, Debug)]
+pub struct AudioDeviceInfo {
+    pub name: String,
+    pub is_default: bool,
+    pub supported_configs: Vec<AudioDeviceConfigRange>,
+}
+
+/// Every input/output device `cpal::default_host()` currently sees, for a Tauri
+/// command to hand to the frontend's device picker. Unlike `find_input_device`/
+/// `find_output_device`, this never falls back to a default on a name mismatch —
+/// it's purely descriptive, so the UI can show what's actually pickable and what
+/// each option supports before a recording starts.
+#[derive(Serialize, Clone, Debug)]
+pub struct AudioDeviceList {
+    pub input_devices: Vec<AudioDeviceInfo>,
+    pub output_devices: Vec<AudioDeviceInfo>,
+}
+
+fn supported_configs<I>(configs: I) -> Vec<AudioDeviceConfigRange>
+where
+    I: Iterator<Item = cpal::SupportedStreamConfigRange>,
+{
+    configs
+        .map(|c| AudioDeviceConfigRange {
+            min_sample_rate: c.min_sample_rate().0,
+            max_sample_rate: c.max_sample_rate().0,
+            channels: c.channels(),
+            sample_format: format!("{:?}", c.sample_format()),
+        })
+        .collect()
+}
+
+pub fn enumerate_audio_devices() -> AudioDeviceList {
+    let host = cpal::default_host();
+    let default_input_name = host.default_input_device().and_then(|d| d.name().ok());
+    let default_output_name = host.default_output_device().and_then(|d| d.name().ok());
+
+    let input_devices = host
+        .input_devices()
+        .map(|devices| {
+            devices
+                .filter_map(|d| {
+                    let name = d.name().ok()?;
+                    Some(AudioDeviceInfo {
+                        is_default: Some(&name) == default_input_name.as_ref(),
+                        supported_configs: d
+                            .supported_input_configs()
+                            .map(supported_configs)
+                            .unwrap_or_default(),
+                        name,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let output_devices = host
+        .output_devices()
+        .map(|devices| {
+            devices
+                .filter_map(|d| {
+                    let name = d.name().ok()?;
+                    Some(AudioDeviceInfo {
+                        is_default: Some(&name) == default_output_name.as_ref(),
+                        supported_configs: d
+                            .supported_output_configs()
+                            .map(supported_configs)
+                            .unwrap_or_default(),
+                        name,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    AudioDeviceList {
+        input_devices,
+        output_devices,
+    }
+}
+
+/// Picks the stream config `build_mic_stream`/`build_sys_stream_linux` should
+/// open a device with: `preference`'s sample rate/channels if the device reports
+/// a supported range covering both, else the device's own default.
+fn pick_stream_config<F, G, I>(
+    preference: Option<&DevicePreference>,
+    supported: F,
+    default: G,
+) -> anyhow::Result<cpal::SupportedStreamConfig>
+where
+    F: FnOnce() -> Result<I, cpal::SupportedStreamConfigsError>,
+    G: FnOnce() -> Result<cpal::SupportedStreamConfig, cpal::DefaultStreamConfigError>,
+    I: Iterator<Item = cpal::SupportedStreamConfigRange>,
+{
+    if let Some(pref) = preference {
+        if let (Some(sample_rate), Some(channels)) = (pref.sample_rate, pref.channels) {
+            if let Ok(mut configs) = supported() {
+                let picked = configs.find(|c| {
+                    c.channels() == channels
+                        && sample_rate >= c.min_sample_rate().0
+                        && sample_rate <= c.max_sample_rate().0
+                });
+                if let Some(range) = picked {
+                    return Ok(range.with_sample_rate(cpal::SampleRate(sample_rate)));
+                }
+                warn!(
+                    "Preferred {}Hz/{}ch not supported by this device, using default config instead",
+                    sample_rate, channels
+                );
+            }
+        }
+    }
+
+    default().map_err(|e| anyhow::anyhow!("Failed to get default config: {}", e))
+}
+
 pub fn run_audio_loop(state: Arc<AppState>, command_rx: Receiver<AudioCommand>, app_handle: AppHandle) {
     let host = cpal::default_host();
 
@@ -93,12 +251,19 @@ pub fn run_audio_loop(state: Arc<AppState>, command_rx: Receiver<AudioCommand>,
     // but for now let's try to just pass a reference? No, thread needs 'static.
     // We will re-acquire the default device in the thread for simplicity.
 
+    // The audio loop's own view of its state, published on `audio_status_tx` at every
+    // transition and re-sent as-is on `AudioCommand::Query`, so a late subscriber
+    // doesn't have to wait for the next transition to know where things stand.
+    let mut current_audio_status = AudioStatus::Stopped;
+
     loop {
         let command = command_rx.recv().unwrap();
 
         match command {
             AudioCommand::Start(id) => {
                 recording_handle = Some(start_segment(id, 1, state.clone(), is_recording.clone()));
+                current_audio_status = AudioStatus::Recording;
+                let _ = state.audio_status_tx.send(current_audio_status.clone());
             }
             AudioCommand::Resume => {
                 let id = *state.current_recording_id.lock().unwrap();
@@ -110,6 +275,8 @@ pub fn run_audio_loop(state: Arc<AppState>, command_rx: Receiver<AudioCommand>,
                         state.clone(),
                         is_recording.clone(),
                     ));
+                    current_audio_status = AudioStatus::Recording;
+                    let _ = state.audio_status_tx.send(current_audio_status.clone());
                 }
             }
             AudioCommand::Pause => {
@@ -118,6 +285,11 @@ pub fn run_audio_loop(state: Arc<AppState>, command_rx: Receiver<AudioCommand>,
                 if let Some(handle) = recording_handle.take() {
                     let _ = handle.join();
                 }
+                current_audio_status = AudioStatus::Paused;
+                let _ = state.audio_status_tx.send(current_audio_status.clone());
+            }
+            AudioCommand::Query => {
+                let _ = state.audio_status_tx.send(current_audio_status.clone());
             }
             AudioCommand::Stop => {
                 is_recording.store(false, Ordering::SeqCst);
@@ -125,6 +297,8 @@ pub fn run_audio_loop(state: Arc<AppState>, command_rx: Receiver<AudioCommand>,
                 if let Some(handle) = recording_handle.take() {
                     let _ = handle.join();
                 }
+                current_audio_status = AudioStatus::Stopped;
+                let _ = state.audio_status_tx.send(current_audio_status.clone());
 
                 // Trigger finalize
                 let id = *state.current_recording_id.lock().unwrap();
@@ -158,9 +332,9 @@ pub fn run_audio_loop(state: Arc<AppState>, command_rx: Receiver<AudioCommand>,
                             
                             if min_minutes > 0 && duration_secs < (min_minutes as u64 * 60) {
                                 info!("Recording too short ({}s < {}m). Discarding.", duration_secs, min_minutes);
-                                match uploader::delete_recording(rec_id, &config).await {
+                                match uploader::delete_recording(rec_id, &state_finalize) {
                                     Ok(_) => {
-                                        info!("Deleted short recording.");
+                                        info!("Queued delete for short recording.");
                                         notifications::show_notification(
                                             &app_handle_finalize,
                                             "Recording Discarded",
@@ -168,20 +342,19 @@ pub fn run_audio_loop(state: Arc<AppState>, command_rx: Receiver<AudioCommand>,
                                         );
                                     },
                                     Err(e) => {
-                                        eprintln!("Failed to delete short recording: {}", e);
-                                        // Fallback to finalize if delete fails? No, better to leave it or try finalize.
-                                        // If delete fails, maybe we should finalize so user can delete it manually.
-                                        match uploader::finalize_recording(rec_id, &config).await {
-                                            Ok(_) => println!("Recording finalized (after delete failed)"),
-                                            Err(e) => eprintln!("Failed to finalize: {}", e),
+                                        eprintln!("Failed to queue delete for short recording: {}", e);
+                                        // Couldn't even enqueue the delete locally, so fall back to
+                                        // queuing a finalize instead of losing the recording entirely.
+                                        match uploader::finalize_recording(rec_id, &state_finalize) {
+                                            Ok(_) => println!("Recording finalize queued (after delete enqueue failed)"),
+                                            Err(e) => eprintln!("Failed to queue finalize: {}", e),
                                         }
                                     },
                                 }
                             } else {
-                                // No sleep needed anymore, we know upload is done
-                                match uploader::finalize_recording(rec_id, &config).await {
-                                    Ok(_) => println!("Recording finalized"),
-                                    Err(e) => eprintln!("Failed to finalize: {}", e),
+                                match uploader::finalize_recording(rec_id, &state_finalize) {
+                                    Ok(_) => println!("Recording finalize queued"),
+                                    Err(e) => eprintln!("Failed to queue finalize: {}", e),
                                 }
                             }
 
@@ -212,6 +385,344 @@ pub fn run_audio_loop(state: Arc<AppState>, command_rx: Receiver<AudioCommand>,
     }
 }
 
+/// Builds, wires up, and plays the mic capture stream. `err_fn` flips `failed`
+/// instead of just logging, so a caller (the initial build in `start_segment`, or
+/// a retry from `run_mic_supervisor`) can tell a `cpal::StreamError` (e.g.
+/// `DeviceNotAvailable` after the device is unplugged) apart from the stream
+/// still playing cleanly. Returns the mic's native sample rate alongside the
+/// stream since `start_segment` needs it to size `spec`/the resample step even
+/// when this is a mid-segment reconnect rather than the first attempt.
+fn build_mic_stream(
+    config: &Config,
+    state: &Arc<AppState>,
+    tx: crossbeam_channel::Sender<Vec<f32>>,
+    failed: Arc<AtomicBool>,
+) -> anyhow::Result<(cpal::Stream, u32)> {
+    let host = cpal::default_host();
+    let mic_device =
+        find_input_device(&host, config).ok_or_else(|| anyhow::anyhow!("No input device available"))?;
+    info!(
+        "Selected Input Device: {}",
+        mic_device.name().unwrap_or_else(|_| "Unknown".to_string())
+    );
+
+    let mic_config: cpal::SupportedStreamConfig = pick_stream_config(
+        config.input_device_preference.as_ref(),
+        || mic_device.supported_input_configs(),
+        || mic_device.default_input_config(),
+    )
+    .or_else(|e| {
+        warn!("{}. Trying to find first supported config...", e);
+        mic_device
+            .supported_input_configs()
+            .map_err(|e| anyhow::anyhow!("Failed to get supported configs: {}", e))?
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No supported input configs found"))
+            .map(|c| c.with_max_sample_rate())
+    })?;
+
+    let mic_channels = mic_config.channels();
+    let mic_sample_rate = mic_config.sample_rate().0;
+    info!("Mic Configured: {}ch, {}Hz", mic_channels, mic_sample_rate);
+
+    let err_fn = move |err| {
+        log::error!("Mic Stream error: {}", err);
+        failed.store(true, Ordering::SeqCst);
+    };
+    let tx = tx.clone();
+    let state_mic = state.clone();
+
+    // Helper to convert interleaved to mono
+    let to_mono_mic = move |data: &[f32], channels: u16| -> Vec<f32> {
+        if channels == 1 {
+            return data.to_vec();
+        }
+        let mut mono = Vec::with_capacity(data.len() / channels as usize);
+        for chunk in data.chunks(channels as usize) {
+            let sum: f32 = chunk.iter().sum();
+            mono.push(sum / channels as f32);
+        }
+        mono
+    };
+
+    let stream = mic_device
+        .build_input_stream(
+            &mic_config.into(),
+            move |data: &[f32], _: &_| {
+                let mono = to_mono_mic(data, mic_channels);
+
+                // Update input level
+                let rms = calculate_rms(&mono);
+                state_mic.record_input_level(rms);
+                let _ = state_mic.audio_status_tx.send(AudioStatus::LevelUpdate {
+                    input: (rms.clamp(0.0, 1.0) * 100.0) as u32,
+                    output: state_mic.output_level.load(Ordering::Relaxed),
+                });
+
+                let _ = tx.send(mono);
+            },
+            err_fn,
+            None,
+        )
+        .map_err(|e| anyhow::anyhow!("Failed to build mic stream: {}", e))?;
+
+    stream
+        .play()
+        .map_err(|e| anyhow::anyhow!("Failed to play mic stream: {}", e))?;
+
+    Ok((stream, mic_sample_rate))
+}
+
+/// Spawns the fallback mic source used when no real input device is available —
+/// either from the very start of a segment or after `run_mic_supervisor` exhausts
+/// its reconnect attempts. Emits silence at `sample_rate` on the same cadence a
+/// real device would, so `run_mixing_loop` and its WAV writer never see a gap in
+/// `mic_rx` wider than the switchover itself.
+fn spawn_virtual_silence_generator(
+    tx: crossbeam_channel::Sender<Vec<f32>>,
+    is_recording: Arc<AtomicBool>,
+    sample_rate: u32,
+) {
+    thread::spawn(move || {
+        info!("Starting Virtual Silence Generator at {}Hz", sample_rate);
+        let chunk_duration_ms = 100;
+        let samples_per_chunk = (sample_rate as f32 * (chunk_duration_ms as f32 / 1000.0)) as usize;
+
+        while is_recording.load(Ordering::SeqCst) {
+            let start = std::time::Instant::now();
+            let _ = tx.send(vec![0.0; samples_per_chunk]);
+
+            let elapsed = start.elapsed();
+            let wait = std::time::Duration::from_millis(chunk_duration_ms as u64);
+            if wait > elapsed {
+                thread::sleep(wait - elapsed);
+            }
+        }
+    });
+}
+
+/// Owns the mic `cpal::Stream` for the rest of the recording, taking over from the
+/// stream `start_segment` built up front to learn `sample_rate`. While it plays
+/// cleanly this just parks; when `build_mic_stream`'s `err_fn` reports a
+/// `cpal::StreamError` (e.g. a USB headset unplugged mid-meeting), the stream is
+/// dropped and rebuilt with a bounded exponential backoff. `mic_tx` is the same
+/// channel across every rebuild, so `run_mixing_loop` and the segment's WAV writer
+/// never notice anything beyond the drop itself — no new segment, no new sequence
+/// number. Falls back to the existing Virtual Silence Generator, rather than
+/// erroring the whole recording out, once `DEVICE_RETRY_MAX_ATTEMPTS` is exhausted.
+fn run_mic_supervisor(
+    initial_stream: cpal::Stream,
+    initial_failed: Arc<AtomicBool>,
+    config: Config,
+    state: Arc<AppState>,
+    mic_tx: crossbeam_channel::Sender<Vec<f32>>,
+    is_recording: Arc<AtomicBool>,
+    sample_rate: u32,
+) {
+    let mut stream = initial_stream;
+    let mut failed = initial_failed;
+
+    loop {
+        while is_recording.load(Ordering::SeqCst) && !failed.load(Ordering::SeqCst) {
+            thread::sleep(std::time::Duration::from_millis(200));
+        }
+        drop(stream);
+        if !is_recording.load(Ordering::SeqCst) {
+            return;
+        }
+        warn!("Mic stream failed; attempting to reconnect...");
+
+        let mut delay_ms = DEVICE_RETRY_INITIAL_MS;
+        let mut rebuilt = None;
+        for attempt in 1..=DEVICE_RETRY_MAX_ATTEMPTS {
+            if !is_recording.load(Ordering::SeqCst) {
+                return;
+            }
+            thread::sleep(std::time::Duration::from_millis(delay_ms));
+
+            let retry_failed = Arc::new(AtomicBool::new(false));
+            match build_mic_stream(&config, &state, mic_tx.clone(), retry_failed.clone()) {
+                Ok((new_stream, recovered_rate)) => {
+                    info!(
+                        "Mic device reconnected after {} attempt(s) ({}Hz)",
+                        attempt, recovered_rate
+                    );
+                    rebuilt = Some((new_stream, retry_failed));
+                    break;
+                }
+                Err(e) => {
+                    warn!("Mic reconnect attempt {} failed: {}", attempt, e);
+                    delay_ms = (delay_ms * 2).min(DEVICE_RETRY_MAX_MS);
+                }
+            }
+        }
+
+        match rebuilt {
+            Some((new_stream, new_failed)) => {
+                stream = new_stream;
+                failed = new_failed;
+            }
+            None => {
+                warn!(
+                    "Mic device did not return after {} attempts; falling back to Virtual Silence Microphone.",
+                    DEVICE_RETRY_MAX_ATTEMPTS
+                );
+                spawn_virtual_silence_generator(mic_tx, is_recording, sample_rate);
+                return;
+            }
+        }
+    }
+}
+
+/// Builds, wires up, and plays the Linux loopback (system-audio) capture stream.
+/// Mirrors `build_mic_stream`: `err_fn` flips `failed` rather than just logging,
+/// so `run_sys_supervisor_linux` can tell a `cpal::StreamError` apart from the
+/// stream still playing cleanly and rebuild in place.
+#[cfg(target_os = "linux")]
+fn build_sys_stream_linux(
+    config: &Config,
+    state: &Arc<AppState>,
+    is_recording: Arc<AtomicBool>,
+    tx: crossbeam_channel::Sender<Vec<f32>>,
+    failed: Arc<AtomicBool>,
+) -> anyhow::Result<cpal::Stream> {
+    let host = cpal::default_host();
+    let sys_device =
+        find_output_device(&host, config).ok_or_else(|| anyhow::anyhow!("No output device available"))?;
+    let sys_config = pick_stream_config(
+        config.output_device_preference.as_ref(),
+        || sys_device.supported_output_configs(),
+        || sys_device.default_output_config(),
+    )?;
+    let sys_channels = sys_config.channels();
+
+    info!(
+        "Sys: {} ({}ch, {}Hz)",
+        sys_device.name().unwrap_or_default(),
+        sys_channels,
+        sys_config.sample_rate().0
+    );
+
+    let err_fn = move |err: cpal::StreamError| {
+        log::error!("Sys Stream error: {}", err);
+        failed.store(true, Ordering::SeqCst);
+    };
+    let state_sys = state.clone();
+
+    // Helper to convert interleaved to mono (redefined for sys stream scope)
+    let to_mono = |data: &[f32], channels: u16| -> Vec<f32> {
+        if channels == 1 {
+            return data.to_vec();
+        }
+        let mut mono = Vec::with_capacity(data.len() / channels as usize);
+        for chunk in data.chunks(channels as usize) {
+            let sum: f32 = chunk.iter().sum();
+            mono.push(sum / channels as f32);
+        }
+        mono
+    };
+
+    let stream = sys_device
+        .build_input_stream(
+            &sys_config.into(),
+            move |data: &[f32], _: &_| {
+                let mono = to_mono(data, sys_channels);
+                // Update output level (always, for monitoring)
+                let rms = calculate_rms(&mono);
+                state_sys.record_output_level(rms);
+                let _ = state_sys.audio_status_tx.send(AudioStatus::LevelUpdate {
+                    input: state_sys.input_level.load(Ordering::Relaxed),
+                    output: (rms.clamp(0.0, 1.0) * 100.0) as u32,
+                });
+
+                if is_recording.load(Ordering::SeqCst) {
+                    let _ = tx.send(mono);
+                }
+            },
+            err_fn,
+            None,
+        )
+        .map_err(|e| anyhow::anyhow!("Failed to build sys stream: {}", e))?;
+
+    stream
+        .play()
+        .map_err(|e| anyhow::anyhow!("Failed to play sys stream: {}", e))?;
+
+    Ok(stream)
+}
+
+/// Owns the Linux loopback `cpal::Stream` for the rest of the recording, the same
+/// way `run_mic_supervisor` owns the mic stream. There's no sys-audio equivalent
+/// of the Virtual Silence Generator to fall back to, but none is needed: once
+/// retries are exhausted this just stops feeding `sys_tx`, and
+/// `run_mixing_loop`'s ring buffer (see `next_resampled_sys_sample`) already treats an
+/// empty sys channel as silence rather than blocking the mic's master clock.
+#[cfg(target_os = "linux")]
+fn run_sys_supervisor_linux(
+    initial_stream: cpal::Stream,
+    initial_failed: Arc<AtomicBool>,
+    config: Config,
+    state: Arc<AppState>,
+    sys_tx: crossbeam_channel::Sender<Vec<f32>>,
+    is_recording: Arc<AtomicBool>,
+) {
+    let mut stream = initial_stream;
+    let mut failed = initial_failed;
+
+    loop {
+        while is_recording.load(Ordering::SeqCst) && !failed.load(Ordering::SeqCst) {
+            thread::sleep(std::time::Duration::from_millis(200));
+        }
+        drop(stream);
+        if !is_recording.load(Ordering::SeqCst) {
+            return;
+        }
+        warn!("Sys audio stream failed; attempting to reconnect...");
+
+        let mut delay_ms = DEVICE_RETRY_INITIAL_MS;
+        let mut rebuilt = None;
+        for attempt in 1..=DEVICE_RETRY_MAX_ATTEMPTS {
+            if !is_recording.load(Ordering::SeqCst) {
+                return;
+            }
+            thread::sleep(std::time::Duration::from_millis(delay_ms));
+
+            let retry_failed = Arc::new(AtomicBool::new(false));
+            match build_sys_stream_linux(
+                &config,
+                &state,
+                is_recording.clone(),
+                sys_tx.clone(),
+                retry_failed.clone(),
+            ) {
+                Ok(new_stream) => {
+                    info!("Sys audio device reconnected after {} attempt(s)", attempt);
+                    rebuilt = Some((new_stream, retry_failed));
+                    break;
+                }
+                Err(e) => {
+                    warn!("Sys audio reconnect attempt {} failed: {}", attempt, e);
+                    delay_ms = (delay_ms * 2).min(DEVICE_RETRY_MAX_MS);
+                }
+            }
+        }
+
+        match rebuilt {
+            Some((new_stream, new_failed)) => {
+                stream = new_stream;
+                failed = new_failed;
+            }
+            None => {
+                warn!(
+                    "Sys audio device did not return after {} attempts; recording continues mic-only.",
+                    DEVICE_RETRY_MAX_ATTEMPTS
+                );
+                return;
+            }
+        }
+    }
+}
+
 fn start_segment(
     recording_id: i64,
     sequence: i32,
@@ -231,132 +742,64 @@ fn start_segment(
             }
             info!("Using temp directory: {:?}", temp_dir);
 
-            let host = cpal::default_host();
-
             // Channels for data transfer
             let (mic_tx, mic_rx) = crossbeam_channel::unbounded::<Vec<f32>>();
             let (sys_tx, sys_rx) = crossbeam_channel::unbounded::<Vec<f32>>();
 
-            // Helper to calculate RMS level (0.0 to 1.0)
-            fn calculate_rms(data: &[f32]) -> f32 {
-                if data.is_empty() {
-                    return 0.0;
-                }
-                let sum_squares: f32 = data.iter().map(|s| s * s).sum();
-                (sum_squares / data.len() as f32).sqrt()
-            }
-
             // 1. Setup Microphone (Input)
-            // We attempt to find a real device. If none found or config fails, we fallback to a virtual silence generator.
-            let (mic_stream, mic_sample_rate) = {
-                let device_opt = find_input_device(&host, &config);
-                
-                match device_opt {
-                    Some(mic_device) => {
-                        info!("Selected Input Device: {}", mic_device.name().unwrap_or_else(|_| "Unknown".to_string()));
-                        
-                        let config_result: anyhow::Result<cpal::SupportedStreamConfig> = mic_device.default_input_config()
-                            .map_err(|e| anyhow::anyhow!("Failed to get default input config: {}", e))
-                            .or_else(|e| {
-                                warn!("{}. Trying to find first supported config...", e);
-                                let config = mic_device.supported_input_configs()
-                                    .map_err(|e| anyhow::anyhow!("Failed to get supported configs: {}", e))?
-                                    .next()
-                                    .ok_or_else(|| anyhow::anyhow!("No supported input configs found"))?
-                                    .with_max_sample_rate();
-                                Ok(config)
-                            });
-
-                        match config_result {
-                            Ok(mic_config) => {
-                                let mic_channels = mic_config.channels();
-                                let mic_sample_rate = mic_config.sample_rate().0;
-                                info!("Mic Configured: {}ch, {}Hz", mic_channels, mic_sample_rate);
-
-                                let err_fn = |err| log::error!("Mic Stream error: {}", err);
-                                let tx = mic_tx.clone();
-                                let state_mic = state.clone();
-                                
-                                // Helper to convert interleaved to mono
-                                let to_mono_mic = move |data: &[f32], channels: u16| -> Vec<f32> {
-                                    if channels == 1 {
-                                        return data.to_vec();
-                                    }
-                                    let mut mono = Vec::with_capacity(data.len() / channels as usize);
-                                    for chunk in data.chunks(channels as usize) {
-                                        let sum: f32 = chunk.iter().sum();
-                                        mono.push(sum / channels as f32);
-                                    }
-                                    mono
-                                };
-
-                                let stream = mic_device.build_input_stream(
-                                    &mic_config.into(),
-                                    move |data: &[f32], _: &_| {
-                                        let mono = to_mono_mic(data, mic_channels);
-                                        
-                                        // Update input level
-                                        let rms = calculate_rms(&mono);
-                                        state_mic.record_input_level(rms);
-
-                                        let _ = tx.send(mono);
-                                    },
-                                    err_fn,
-                                    None,
-                                ).map_err(|e| anyhow::anyhow!("Failed to build mic stream: {}", e))?;
-
-                                (Some(stream), mic_sample_rate)
-                            },
-                            Err(e) => {
-                                warn!("Failed to configure microphone: {}. Falling back to Virtual Silence Microphone.", e);
-                                (None, 48000)
-                            }
-                        }
-                    },
-                    None => {
-                        warn!("No input device found. Falling back to Virtual Silence Microphone.");
+            // We attempt to find a real device. If none found or config fails, we fall
+            // back to a virtual silence generator; `run_mic_supervisor` below reaches
+            // for the same fallback if a device we did acquire drops out mid-segment.
+            let mic_failed = Arc::new(AtomicBool::new(false));
+            let (mic_stream, mic_sample_rate) =
+                match build_mic_stream(&config, &state, mic_tx.clone(), mic_failed.clone()) {
+                    Ok((stream, rate)) => (Some(stream), rate),
+                    Err(e) => {
+                        warn!("Failed to configure microphone: {}. Falling back to Virtual Silence Microphone.", e);
                         (None, 48000)
                     }
-                }
-            };
+                };
 
-            // If using virtual mic, spawn the generator
-            if mic_stream.is_none() {
-                let tx = mic_tx.clone();
-                let is_rec = is_recording.clone();
-                let sample_rate = mic_sample_rate;
-                
-                thread::spawn(move || {
-                    info!("Starting Virtual Silence Generator at {}Hz", sample_rate);
-                    let chunk_duration_ms = 100;
-                    let samples_per_chunk = (sample_rate as f32 * (chunk_duration_ms as f32 / 1000.0)) as usize;
-                    
-                    while is_rec.load(Ordering::SeqCst) {
-                        let start = std::time::Instant::now();
-                        let _ = tx.send(vec![0.0; samples_per_chunk]);
-                        
-                        let elapsed = start.elapsed();
-                        let wait = std::time::Duration::from_millis(chunk_duration_ms as u64);
-                        if wait > elapsed {
-                            thread::sleep(wait - elapsed);
-                        }
-                    }
-                });
+            match mic_stream {
+                Some(stream) => {
+                    let config_sup = config.clone();
+                    let state_sup = state.clone();
+                    let mic_tx_sup = mic_tx.clone();
+                    let is_recording_sup = is_recording.clone();
+                    thread::spawn(move || {
+                        run_mic_supervisor(
+                            stream,
+                            mic_failed,
+                            config_sup,
+                            state_sup,
+                            mic_tx_sup,
+                            is_recording_sup,
+                            mic_sample_rate,
+                        );
+                    });
+                }
+                None => {
+                    spawn_virtual_silence_generator(mic_tx.clone(), is_recording.clone(), mic_sample_rate);
+                }
             }
 
             // 2. Setup System Audio (Loopback) - use configured or default
-            // On Windows WASAPI, we use the output device for loopback
-            #[cfg(not(target_os = "macos"))]
-            let sys_device = find_output_device(&host, &config)
+            // Linux (PulseAudio/PipeWire via cpal) captures loopback by opening
+            // an input stream on the output device; macOS and Windows each need
+            // a dedicated backend (ScreenCaptureKit / WASAPI loopback) below.
+            #[cfg(target_os = "linux")]
+            let sys_device = find_output_device(&cpal::default_host(), &config)
                 .ok_or_else(|| anyhow::anyhow!("No output device available"))?;
-            #[cfg(not(target_os = "macos"))]
-            let sys_config = sys_device
-                .default_output_config()
-                .map_err(|e| anyhow::anyhow!("Failed to get sys config: {}", e))?;
-            #[cfg(not(target_os = "macos"))]
+            #[cfg(target_os = "linux")]
+            let sys_config = pick_stream_config(
+                config.output_device_preference.as_ref(),
+                || sys_device.supported_output_configs(),
+                || sys_device.default_output_config(),
+            )?;
+            #[cfg(target_os = "linux")]
             let sys_channels = sys_config.channels();
-            
-            #[cfg(not(target_os = "macos"))]
+
+            #[cfg(target_os = "linux")]
             info!(
                 "Sys: {} ({}ch, {}Hz)",
                 sys_device.name().unwrap_or_default(),
@@ -364,70 +807,45 @@ fn start_segment(
                 sys_config.sample_rate().0
             );
 
-            // Target format: Mono, 16-bit, Mic Sample Rate (Master Clock)
-            let spec = hound::WavSpec {
-                channels: 1,
-                sample_rate: mic_sample_rate,
-                bits_per_sample: 16,
-                sample_format: hound::SampleFormat::Int,
-            };
-
-            let err_fn = |err: cpal::StreamError| log::error!("Stream error: {}", err);
-
-            // Helper to convert interleaved to mono (redefined for sys stream scope)
-            let to_mono = |data: &[f32], channels: u16| -> Vec<f32> {
-                if channels == 1 {
-                    return data.to_vec();
-                }
-                let mut mono = Vec::with_capacity(data.len() / channels as usize);
-                for chunk in data.chunks(channels as usize) {
-                    let sum: f32 = chunk.iter().sum();
-                    mono.push(sum / channels as f32);
-                }
-                mono
-            };
+            // Rate `run_mixing_loop` resamples system audio from before mixing. macOS/Windows
+            // loopback capture already resamples to `mic_sample_rate` itself (see
+            // `mac_sc::start_capture`/`win_audio::start_capture`), so only Linux's raw
+            // cpal loopback stream runs at its own device rate here.
+            #[cfg(target_os = "linux")]
+            let sys_sample_rate: u32 = sys_config.sample_rate().0;
+            #[cfg(not(target_os = "linux"))]
+            let sys_sample_rate: u32 = mic_sample_rate;
 
-            // 3. Build Sys Stream
-            #[cfg(not(target_os = "macos"))]
-            let _sys_stream = {
-                let is_recording_sys = is_recording.clone();
-                let state_sys = state.clone();
-                
-                let sys_device = find_output_device(&host, &config)
-                    .ok_or_else(|| anyhow::anyhow!("No output device available"))?;
-                let sys_config = sys_device
-                    .default_output_config()
-                    .map_err(|e| anyhow::anyhow!("Failed to get sys config: {}", e))?;
-                let sys_channels = sys_config.channels();
 
-                info!(
-                    "Sys: {} ({}ch, {}Hz)",
-                    sys_device.name().unwrap_or_default(),
-                    sys_channels,
-                    sys_config.sample_rate().0
-                );
+            // 3. Build Sys Stream. Like the mic, ownership and error recovery are
+            // handed off to a supervisor thread so a dropped loopback device (e.g.
+            // switching output devices mid-meeting) doesn't kill the recording.
+            #[cfg(target_os = "linux")]
+            {
+                let sys_failed = Arc::new(AtomicBool::new(false));
+                let sys_stream = build_sys_stream_linux(
+                    &config,
+                    &state,
+                    is_recording.clone(),
+                    sys_tx.clone(),
+                    sys_failed.clone(),
+                )?;
 
-                let sys_stream = sys_device
-                    .build_input_stream(
-                        &sys_config.into(),
-                        move |data: &[f32], _: &_| {
-                            let mono = to_mono(data, sys_channels);
-                            // Update output level (always, for monitoring)
-                            let rms = calculate_rms(&mono);
-                            state_sys.record_output_level(rms);
-
-                            if is_recording_sys.load(Ordering::SeqCst) {
-                                let _ = sys_tx.send(mono);
-                            }
-                        },
-                        err_fn,
-                        None,
-                    )
-                    .map_err(|e| anyhow::anyhow!("Failed to build sys stream: {}", e))?;
-                
-                sys_stream.play().map_err(|e| anyhow::anyhow!("Failed to play sys stream: {}", e))?;
-                sys_stream
-            };
+                let config_sup = config.clone();
+                let state_sup = state.clone();
+                let sys_tx_sup = sys_tx.clone();
+                let is_recording_sup = is_recording.clone();
+                thread::spawn(move || {
+                    run_sys_supervisor_linux(
+                        sys_stream,
+                        sys_failed,
+                        config_sup,
+                        state_sup,
+                        sys_tx_sup,
+                        is_recording_sup,
+                    );
+                });
+            }
 
             #[cfg(target_os = "macos")]
             let sys_stream = {
@@ -443,41 +861,44 @@ fn start_segment(
                 // Start capture using the synchronous API (screencapturekit 1.3)
                 let stream = crate::mac_sc::start_capture(tx, target_sample_rate, 2)
                     .map_err(|e| anyhow::anyhow!("Failed to start SCK: {}", e))?;
-                
+
                 stream
             };
 
-            if let Some(stream) = mic_stream {
-                stream
-                    .play()
-                    .map_err(|e| anyhow::anyhow!("Failed to play mic stream: {}", e))?;
-                
-                // 5. Mixing Loop with automatic segmentation
-                run_mixing_loop(
-                    recording_id,
-                    sequence,
-                    spec,
-                    mic_rx,
-                    sys_rx,
-                    is_recording,
-                    state.clone(),
-                    MAX_SEGMENT_DURATION_SECS,
-                    temp_dir,
-                )?;
+            #[cfg(target_os = "windows")]
+            let _sys_stream = {
+                let target_sample_rate = mic_sample_rate;
+                info!("Starting WASAPI loopback capture for System Audio at {}Hz", target_sample_rate);
+
+                crate::win_audio::start_capture(sys_tx.clone(), target_sample_rate, 2)
+                    .map_err(|e| anyhow::anyhow!("Failed to start WASAPI loopback: {}", e))?
+            };
+
+            // Local transcription is opt-in (it downloads and runs a Whisper model),
+            // so only spin the thread up when the user has turned it on.
+            let transcribe_tx = if config.transcription_enabled {
+                Some(crate::transcribe::spawn(state.clone(), mic_sample_rate))
             } else {
-                // Virtual mic mode
-                run_mixing_loop(
-                    recording_id,
-                    sequence,
-                    spec,
-                    mic_rx,
-                    sys_rx,
-                    is_recording,
-                    state.clone(),
-                    MAX_SEGMENT_DURATION_SECS,
-                    temp_dir,
-                )?;
-            }
+                None
+            };
+
+            // 5. Mixing Loop with automatic segmentation. The mic stream (real or
+            // virtual) is owned and kept alive by `run_mic_supervisor`/
+            // `spawn_virtual_silence_generator` above; this loop only ever touches
+            // `mic_rx`, so it doesn't need to know which one is feeding it.
+            run_mixing_loop(
+                recording_id,
+                sequence,
+                mic_sample_rate,
+                mic_rx,
+                sys_rx,
+                is_recording,
+                state.clone(),
+                MAX_SEGMENT_DURATION_SECS,
+                temp_dir,
+                transcribe_tx,
+                sys_sample_rate,
+            )?;
 
             Ok(())
         };
@@ -485,46 +906,317 @@ fn start_segment(
         if let Err(e) = run() {
             log::error!("Recording thread error: {}", e);
             // Update status to Error
+            state.recordings_failed_total.fetch_add(1, Ordering::Relaxed);
             let mut status = state.status.lock().unwrap();
             *status = AppStatus::Error(e.to_string());
+            drop(status);
+            let _ = state.audio_status_tx.send(AudioStatus::Error(e.to_string()));
         }
     })
 }
 
+/// Reads the next system-audio sample at the mic's rate by linearly interpolating
+/// between the two `sys_buffer` samples surrounding the fractional cursor `pos`
+/// (in sys-rate sample units), then advances `pos` by `step` (`sys_rate / mic_rate`).
+/// Returns `None` on underrun (not enough buffered sys samples yet) without advancing
+/// `pos`, so the caller can emit mic-only (i.e. mix in silence for the sys side) and
+/// try again once more sys audio arrives.
+/// Fully-consumed leading samples are popped off the front of `sys_buffer` so it
+/// doesn't grow unbounded over a long segment; the fractional remainder of `pos`
+/// carries over. `sys_buffer` is a `VecDeque` rather than a `Vec` so this is O(1)
+/// amortized instead of shifting the whole buffer down on every sample.
+fn next_resampled_sys_sample(sys_buffer: &mut VecDeque<f32>, pos: &mut f64, step: f64) -> Option<f32> {
+    let i = pos.floor() as usize;
+    if i + 1 >= sys_buffer.len() {
+        return None;
+    }
+
+    let frac = (*pos - i as f64) as f32;
+    let sample = sys_buffer[i] * (1.0 - frac) + sys_buffer[i + 1] * frac;
+    *pos += step;
+
+    let consumed = pos.floor() as usize;
+    if consumed > 0 {
+        let drained = consumed.min(sys_buffer.len());
+        sys_buffer.drain(..drained);
+        *pos -= drained as f64;
+    }
+
+    Some(sample)
+}
+
+/// Drops the oldest buffered sys-audio frames so `sys_buffer` never represents more
+/// than `max_samples` worth of backlog. Called after every batch of incoming sys
+/// audio is appended; a backlog this large means the sys stream has fallen behind
+/// the mic clock (e.g. after a scheduling hiccup) and is resynced by discarding the
+/// overflow rather than letting it play back increasingly late.
+fn enforce_backlog_cap(sys_buffer: &mut VecDeque<f32>, pos: &mut f64, max_samples: usize) {
+    if sys_buffer.len() <= max_samples {
+        return;
+    }
+    let drop_count = sys_buffer.len() - max_samples;
+    sys_buffer.drain(..drop_count);
+    *pos = (*pos - drop_count as f64).max(0.0);
+}
+
+/// Magnitude above which the limiter starts reducing gain, left as headroom below
+/// 1.0 so a sample is pulled down before it would actually reach full scale rather
+/// than only once it's already past it.
+const LIMITER_THRESHOLD: f32 = 0.9;
+/// How much gain is recovered per sample once a peak has passed. Release is gradual
+/// so gain reduction fades back out smoothly instead of snapping back to 1.0 and
+/// immediately re-triggering on the next loud sample ("pumping").
+const LIMITER_RELEASE_PER_SAMPLE: f32 = 0.0005;
+
+/// Smoothly compresses `sample` toward `LIMITER_THRESHOLD` as it approaches +-1.0,
+/// replacing the hard clip `run_mixing_loop` used to apply. `gain` is the limiter's
+/// current gain reduction, carried in by the caller across the whole recording; attack
+/// (gain dropping) is instant so a sample can never actually exceed the threshold, but
+/// release (gain recovering toward 1.0) is capped at `LIMITER_RELEASE_PER_SAMPLE` per
+/// sample to avoid audible pumping.
+fn apply_soft_limiter(sample: f32, gain: &mut f32) -> f32 {
+    let abs = sample.abs();
+    let target_gain = if abs > LIMITER_THRESHOLD {
+        LIMITER_THRESHOLD / abs
+    } else {
+        1.0
+    };
+
+    *gain = if target_gain < *gain {
+        target_gain
+    } else {
+        (*gain + LIMITER_RELEASE_PER_SAMPLE).min(target_gain)
+    };
+
+    sample * *gain
+}
+
+/// Drains one segment's ring buffer, encodes every chunk it receives, and queues the
+/// finished file for upload. Spawned on the existing Tauri async runtime by
+/// `run_mixing_loop` so encoding a slow codec (FLAC, Opus) and the disk write in
+/// `SegmentEncoder::finalize` never block the real-time mixing thread; the consumer
+/// just stops once `consumer.pop()` returns `None`, which happens once the mixing loop
+/// drops its producer at the end of the segment and every already-buffered chunk has
+/// drained.
+async fn run_segment_encoder_task(
+    mut consumer: async_ringbuf::AsyncHeapConsumer<Vec<f32>>,
+    codec: SegmentCodec,
+    path: std::path::PathBuf,
+    sample_rate: u32,
+    dither_enabled: bool,
+    recording_id: i64,
+    sequence: i32,
+    state: Arc<AppState>,
+    manifest: Arc<Mutex<manifest::LiveManifest>>,
+) {
+    let mut samples_written: u64 = 0;
+    let encode_result: anyhow::Result<()> = async {
+        let mut encoder =
+            crate::encoder::create_segment_encoder(codec, &path, sample_rate, dither_enabled)?;
+        while let Some(chunk) = consumer.pop().await {
+            samples_written += chunk.len() as u64;
+            encoder.write_frame(&chunk)?;
+        }
+        encoder.finalize()?;
+        Ok(())
+    }
+    .await;
+
+    if let Err(e) = encode_result {
+        log::error!("Failed to encode segment {}: {}", sequence, e);
+        return;
+    }
+
+    info!("Segment {} recorded: {:?}", sequence, path);
+    let segment_bytes = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    match uploader::upload_segment(recording_id, sequence, &path, codec, &state) {
+        Ok(_) => {
+            state.uploaded_chunks_total.fetch_add(1, Ordering::Relaxed);
+            state.uploaded_bytes_total.fetch_add(segment_bytes, Ordering::Relaxed);
+        }
+        Err(e) => log::error!("Failed to queue segment {} for upload: {}", sequence, e),
+    }
+
+    // Keeps the live manifest following along with whatever's actually landed, so a
+    // player can start on segment 1 without waiting for the recording to finish.
+    let duration_secs = samples_written as f64 / sample_rate as f64;
+    let manifest_body = {
+        let mut manifest = manifest.lock().unwrap();
+        manifest.push_segment(sequence, segment_uri(recording_id, sequence, codec), duration_secs);
+        manifest.render()
+    };
+    if let Err(e) = uploader::upload_manifest(recording_id, manifest_body, &state) {
+        log::error!("Failed to queue manifest update for recording {}: {}", recording_id, e);
+    }
+
+    // Additive, best-effort low-latency delivery; the reliable uploader above is
+    // still the only thing that determines whether the segment counts as archived.
+    let live_sender = state.live_stream_sender.lock().unwrap().clone();
+    if let Some(sender) = live_sender {
+        match std::fs::read(&path) {
+            Ok(data) => sender.send_segment(sequence, data).await,
+            Err(e) => log::warn!("Failed to read segment {} for live streaming: {}", sequence, e),
+        }
+    }
+}
+
+/// URI a segment is reachable at once uploaded, matching `do_upload_segment`'s
+/// `/recordings/{id}/segment?sequence=...&codec=...` route. `LiveManifest` embeds this
+/// directly rather than a bare filename so a player can resolve it without knowing the
+/// backend's routing scheme itself.
+fn segment_uri(recording_id: i64, sequence: i32, codec: SegmentCodec) -> String {
+    format!(
+        "recordings/{}/segment?sequence={}&codec={}",
+        recording_id,
+        sequence,
+        codec.extension()
+    )
+}
+
 // Helper function for the mixing loop to avoid code duplication
 fn run_mixing_loop(
     recording_id: i64,
     mut current_sequence: i32,
-    spec: hound::WavSpec,
+    sample_rate: u32,
     mic_rx: crossbeam_channel::Receiver<Vec<f32>>,
     sys_rx: crossbeam_channel::Receiver<Vec<f32>>,
     is_recording: Arc<AtomicBool>,
     state: Arc<AppState>,
     max_duration: u64,
     temp_dir: std::path::PathBuf,
+    transcribe_tx: Option<crossbeam_channel::Sender<Vec<f32>>>,
+    sys_sample_rate: u32,
 ) -> anyhow::Result<()> {
-    let mut sys_buffer: Vec<f32> = Vec::new();
-    let rt = tokio::runtime::Runtime::new().unwrap();
+    let segment_codec = state.config.lock().unwrap().segment_codec;
+    let dither_enabled = state.config.lock().unwrap().dither_enabled;
+
+    // One manifest per recording, appended to as each segment finishes and
+    // re-uploaded so a player following the live recording always sees the latest
+    // state without waiting for it to finish.
+    let manifest = Arc::new(Mutex::new(manifest::LiveManifest::new(current_sequence)));
+    // Compressed codecs carry per-segment headers of their own today (see `encoder`),
+    // but a shared init segment keeps the manifest forward-compatible with a future
+    // header-stripped encoder without another manifest format change; sequence 0 is
+    // otherwise unused since real segments start at 1.
+    if segment_codec != crate::encoder::SegmentCodec::Wav {
+        let init_filename = format!("temp_{}_init.{}", recording_id, segment_codec.extension());
+        let init_path = temp_dir.join(&init_filename);
+        match crate::encoder::create_segment_encoder(segment_codec, &init_path, sample_rate, dither_enabled)
+            .and_then(|encoder| encoder.finalize())
+        {
+            Ok(_) => {
+                if let Err(e) = uploader::upload_segment(recording_id, 0, &init_path, segment_codec, &state) {
+                    log::error!("Failed to queue init segment for recording {}: {}", recording_id, e);
+                }
+                manifest.lock().unwrap().set_init_uri(segment_uri(recording_id, 0, segment_codec));
+            }
+            Err(e) => log::error!("Failed to write init segment for recording {}: {}", recording_id, e),
+        }
+    }
+
+    let buffering_config = state.config.lock().unwrap().audio_buffering.clone();
+    let target_latency_samples =
+        (buffering_config.target_latency_ms as f64 / 1000.0 * sys_sample_rate as f64) as usize;
+    let max_backlog_samples = ((buffering_config.target_latency_ms + buffering_config.batch_ms) as f64
+        / 1000.0
+        * sys_sample_rate as f64) as usize;
+
+    let vad_config = state.config.lock().unwrap().vad.clone();
+    // One detector for the whole recording (not reset per segment) so the noise
+    // floor it learns carries over across a 5-minute split instead of re-adapting
+    // from a cold start every time.
+    let mut vad = if vad_config.enabled {
+        Some(vad::VoiceActivityDetector::new(sample_rate))
+    } else {
+        None
+    };
+    let max_silence_samples =
+        (vad_config.max_silence_ms as u64 * sample_rate as u64 / 1000) as usize;
+    // Most recent VAD verdict, carried across mixing calls since a frame's
+    // classification usually lags slightly behind the chunk that completed it.
+    let mut vad_is_speech = true;
+    // How many silent samples have been written so far in the current run of
+    // silence; once it passes `max_silence_samples` further silent samples are
+    // dropped instead of written, until speech resumes.
+    let mut silence_written = 0usize;
+
+    let mut sys_buffer: VecDeque<f32> = VecDeque::new();
+    // Fractional read cursor into `sys_buffer`, in units of sys-rate samples. Carried
+    // across mixing calls (not reset per-callback) so the interpolation phase stays
+    // continuous across callback boundaries.
+    let mut sys_pos: f64 = 0.0;
+    let resample_step = sys_sample_rate as f64 / sample_rate as f64;
+    // Once the buffer has reached `target_latency_samples` it's considered primed and
+    // stays that way for the rest of the segment; dropping back to `false` on every
+    // dip below target would reopen the same pre-buffering gap it's meant to avoid.
+    let mut primed = false;
+
+    // Soft-knee limiter's current gain reduction (1.0 = no reduction), carried across
+    // the whole recording rather than reset per segment so a loud moment right at a
+    // segment boundary doesn't snap back to full gain and clip on the next sample.
+    // See `apply_soft_limiter`.
+    let mut limiter_gain: f32 = 1.0;
+
+    // Encoder tasks for segments already handed off but not necessarily finished
+    // encoding/uploading yet. Joined after the loop exits so `run_mixing_loop` doesn't
+    // return (and let `AudioCommand::Stop` queue `finalize_recording`) before the last
+    // segment has actually been queued for upload.
+    let mut pending_encoder_tasks: Vec<tauri::async_runtime::JoinHandle<()>> = Vec::new();
 
     while is_recording.load(Ordering::SeqCst) {
-        let filename = format!("temp_{}_{}.wav", recording_id, current_sequence);
+        let filename = format!(
+            "temp_{}_{}.{}",
+            recording_id,
+            current_sequence,
+            segment_codec.extension()
+        );
         let path = temp_dir.join(&filename);
 
-        let mut writer = hound::WavWriter::create(&path, spec)
-            .map_err(|e| anyhow::anyhow!("Failed to create wav writer: {}", e))?;
+        // The mixing loop below only ever produces into this segment's ring buffer;
+        // `run_segment_encoder_task` owns the consumer, the actual encoder, and the
+        // upload hand-off, running on the existing Tauri async runtime so neither the
+        // codec's CPU cost nor `SegmentEncoder::finalize`'s disk write can stall the
+        // real-time mixing thread.
+        let segment_rb = async_ringbuf::AsyncHeapRb::<Vec<f32>>::new(SEGMENT_RING_BUFFER_CAPACITY);
+        let (mut producer, consumer) = segment_rb.split();
+        pending_encoder_tasks.push(tauri::async_runtime::spawn(run_segment_encoder_task(
+            consumer,
+            segment_codec,
+            path.clone(),
+            sample_rate,
+            dither_enabled,
+            recording_id,
+            current_sequence,
+            state.clone(),
+            manifest.clone(),
+        )));
 
         let segment_start = std::time::Instant::now();
 
         // Record for up to MAX_SEGMENT_DURATION_SECS or until stopped
         while is_recording.load(Ordering::SeqCst) {
             // Check if we've exceeded the maximum segment duration
-            if segment_start.elapsed().as_secs() >= max_duration {
+            let elapsed_secs = segment_start.elapsed().as_secs();
+            if elapsed_secs >= max_duration {
                 info!(
                     "Segment {} reached maximum duration, starting new segment",
                     current_sequence
                 );
                 break;
             }
+            // Once we're within `prefer_split_window_secs` of the hard cap, cut on
+            // the next detected silence instead of waiting for the cap itself, so
+            // the split lands on a pause rather than mid-word.
+            if vad.is_some()
+                && !vad_is_speech
+                && elapsed_secs + vad_config.prefer_split_window_secs >= max_duration
+            {
+                info!(
+                    "Segment {} nearing maximum duration; splitting at detected silence",
+                    current_sequence
+                );
+                break;
+            }
 
             // Block on Mic data (Master)
             if let Ok(mic_data) = mic_rx.recv_timeout(std::time::Duration::from_millis(500))
@@ -533,52 +1225,93 @@ fn run_mixing_loop(
                 while let Ok(sys_chunk) = sys_rx.try_recv() {
                     sys_buffer.extend(sys_chunk);
                 }
+                enforce_backlog_cap(&mut sys_buffer, &mut sys_pos, max_backlog_samples);
+
+                if !primed && sys_buffer.len() >= target_latency_samples {
+                    primed = true;
+                }
+
+                let fill_ms = (sys_buffer.len() as f64 / sys_sample_rate as f64 * 1000.0) as u32;
+                state.sys_buffer_fill_ms.store(fill_ms, Ordering::Relaxed);
 
-                // Mix
-                for (_i, mic_sample) in mic_data.iter().enumerate() {
+                // Mix: one sys sample per mic sample, resampled from the sys stream's
+                // own rate onto the mic's "master clock" via linear interpolation.
+                let mut mixed_chunk: Vec<f32> = Vec::with_capacity(mic_data.len());
+                for mic_sample in mic_data.iter() {
                     let mut mixed = *mic_sample;
 
-                    // Simple mixing: Add system audio if available
-                    // Note: This is a naive mix. Real mixing needs resampling if rates differ.
-                    // We assume rates are close enough or identical for now.
-                    if !sys_buffer.is_empty() {
-                        let sys_sample = sys_buffer.remove(0);
-                        mixed += sys_sample;
+                    if primed {
+                        if let Some(sys_sample) =
+                            next_resampled_sys_sample(&mut sys_buffer, &mut sys_pos, resample_step)
+                        {
+                            mixed += sys_sample;
+                        }
                     }
+                    // Else: still pre-buffering, or underrun mid-segment. Mix in silence
+                    // on the sys side (i.e. emit mic-only) and leave `sys_pos` where it
+                    // is rather than advancing past data we don't have yet.
 
-                    // Hard clip to avoid wrapping
-                    if mixed > 1.0 {
-                        mixed = 1.0;
-                    } else if mixed < -1.0 {
-                        mixed = -1.0;
-                    }
+                    mixed = apply_soft_limiter(mixed, &mut limiter_gain);
 
-                    // Convert f32 (-1.0 to 1.0) to i16
-                    let sample_i16 = (mixed * i16::MAX as f32) as i16;
-                    writer.write_sample(sample_i16).unwrap();
+                    mixed_chunk.push(mixed);
                 }
-            }
-        }
 
-        writer.finalize().map_err(|e| anyhow::anyhow!("Failed to finalize wav writer: {}", e))?;
-        info!("Segment {} recorded: {:?}", current_sequence, path);
+                // Classify this chunk and decide how much of it to write. Trimming
+                // (or splitting) never touches `transcribe_tx` below, only the WAV
+                // file, so live captions still see every word.
+                let write_len = if let Some(vad) = vad.as_mut() {
+                    for frame in vad.process(&mixed_chunk) {
+                        vad_is_speech = frame.is_speech;
+                        state.speech_energy.store(frame.speech_energy, Ordering::Relaxed);
+                    }
+
+                    if vad_is_speech {
+                        silence_written = 0;
+                        mixed_chunk.len()
+                    } else if silence_written < max_silence_samples {
+                        let allowed = (max_silence_samples - silence_written).min(mixed_chunk.len());
+                        silence_written += allowed;
+                        allowed
+                    } else {
+                        0
+                    }
+                } else {
+                    mixed_chunk.len()
+                };
 
-        // Upload segment in background
-        let _state_upload = state.clone();
-        let path_clone = path.clone();
-        let seq = current_sequence;
-        let config = state.config.lock().unwrap().clone();
+                let to_write = mixed_chunk[..write_len].to_vec();
+                // Pushes onto the bounded ring buffer rather than writing to disk
+                // directly; blocks (via the async runtime) only once the encoder task
+                // has fallen `SEGMENT_RING_BUFFER_CAPACITY` chunks behind, which is the
+                // intended backpressure for a slow disk/codec instead of a glitch.
+                if tauri::async_runtime::block_on(producer.push(to_write)).is_err() {
+                    warn!("Segment {} encoder task ended early; dropping remaining audio", current_sequence);
+                }
 
-        rt.spawn(async move {
-            match uploader::upload_segment(recording_id, seq, &path_clone, &config).await {
-                Ok(_) => info!("Segment {} uploaded successfully", seq),
-                Err(e) => log::error!("Failed to upload segment {}: {}", seq, e),
+                if let Some(tx) = &transcribe_tx {
+                    let _ = tx.send(mixed_chunk);
+                }
             }
-        });
+        }
+
+        // Dropping the producer is the end-of-segment signal: once every chunk already
+        // pushed has drained, `run_segment_encoder_task`'s `consumer.pop()` returns
+        // `None` and it finalizes and queues the upload on its own.
+        drop(producer);
 
         current_sequence += 1;
         *state.current_sequence.lock().unwrap() = current_sequence;
     }
+
+    // Wait for every segment's encoder task to finish encoding and queue its upload
+    // before returning, so the `AudioCommand::Stop` handler's `finalize_recording`
+    // call can't race ahead of the last segment's upload enqueue.
+    tauri::async_runtime::block_on(async {
+        for task in pending_encoder_tasks {
+            let _ = task.await;
+        }
+    });
+
     Ok(())
 }
 
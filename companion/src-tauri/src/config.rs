@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
+use std::time::SystemTime;
 use log::info;
 use directories::ProjectDirs;
 
@@ -23,6 +24,220 @@ pub struct Config {
     pub output_device_name: Option<String>,
     #[serde(default)]
     pub last_version: Option<String>,
+    /// Pushgateway base URL (e.g. "http://localhost:9091"). When set, a background
+    /// task periodically pushes the same series exposed at `/metrics`.
+    #[serde(default)]
+    pub pushgateway_url: Option<String>,
+    /// How long a scoped control-server token minted by `/token` stays valid.
+    #[serde(default = "default_scoped_token_ttl_secs")]
+    pub scoped_token_ttl_secs: u64,
+    /// OAuth access token for the backend API. Takes priority over the legacy static
+    /// `api_token` when present; refreshed transparently by `AppState::valid_token`.
+    #[serde(default)]
+    pub access_token: Option<String>,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    #[serde(default)]
+    pub expires_at: Option<SystemTime>,
+    /// Overrides the embedded minisign public key artifacts are verified against
+    /// before install. Leave unset to trust Nojoin's own release-signing key.
+    #[serde(default)]
+    pub pubkey: Option<String>,
+    /// Release channel `updater::check_latest_release` checks against. `Beta`
+    /// additionally considers GitHub releases flagged as prerelease; `Stable`
+    /// only ever installs a non-prerelease tag.
+    #[serde(default)]
+    pub channel: ReleaseChannel,
+    /// Backend health-check poll interval while connected, in seconds. The loop
+    /// backs off from this toward `health_check_max_interval_secs` on failure,
+    /// multiplying by `health_check_backoff_multiplier` each time it fails again.
+    #[serde(default = "default_health_check_base_interval_secs")]
+    pub health_check_base_interval_secs: u64,
+    #[serde(default = "default_health_check_max_interval_secs")]
+    pub health_check_max_interval_secs: u64,
+    #[serde(default = "default_health_check_backoff_multiplier")]
+    pub health_check_backoff_multiplier: f64,
+    /// Version the user chose "Remind Me Later" for, set by `update_prompt`.
+    /// `check_and_prompt_update` skips re-prompting on silent checks while this
+    /// still matches the detected version; a manual check always prompts anyway.
+    #[serde(default)]
+    pub dismissed_update_version: Option<String>,
+    /// Whether `transcribe::spawn` decodes live captions/transcript locally. Off by
+    /// default since it downloads and runs a Whisper model on the user's machine.
+    #[serde(default)]
+    pub transcription_enabled: bool,
+    /// Whisper model size passed to `transcribe::Whisper::load`, e.g. "tiny", "base",
+    /// "small". Larger models are more accurate but slower and heavier to download.
+    #[serde(default = "default_transcription_model")]
+    pub transcription_model: String,
+    /// Governs the system-audio ring buffer in `audio::run_mixing_loop`.
+    #[serde(default)]
+    pub audio_buffering: AudioBufferingConfig,
+    /// Governs `vad::VoiceActivityDetector` in `audio::run_mixing_loop`.
+    #[serde(default)]
+    pub vad: VadConfig,
+    /// Preferred sample rate/channel count for `input_device_name`, picked from the
+    /// options `audio::enumerate_audio_devices` reported for it. `None` (the
+    /// default) keeps taking `default_input_config`.
+    #[serde(default)]
+    pub input_device_preference: Option<DevicePreference>,
+    /// Same as `input_device_preference`, for `output_device_name`.
+    #[serde(default)]
+    pub output_device_preference: Option<DevicePreference>,
+    /// Codec `audio::run_mixing_loop` writes each segment with. `Wav` (the default)
+    /// matches every segment recorded before this existed; `Flac`/`Opus` trade that
+    /// for a much smaller upload at the cost of an extra encode step.
+    #[serde(default)]
+    pub segment_codec: crate::encoder::SegmentCodec,
+    /// Governs `live_stream::LiveStreamSender`, the low-latency delivery path
+    /// `run_segment_encoder_task` feeds alongside `uploader::upload_segment`.
+    #[serde(default)]
+    pub live_stream: LiveStreamConfig,
+    /// Whether `encoder`'s i16 quantization adds triangular-PDF dither before
+    /// rounding. On by default since it removes audible quantization noise on quiet
+    /// passages; off trades that for bit-exact, reproducible sample values.
+    #[serde(default = "default_dither_enabled")]
+    pub dither_enabled: bool,
+}
+
+fn default_dither_enabled() -> bool {
+    true
+}
+
+/// A sample rate/channel count pinned for one device, set from the UI's device
+/// picker. `build_mic_stream`/`build_sys_stream_linux` use this instead of the
+/// device's default config when both fields resolve to a config the device
+/// actually supports, falling back to the default otherwise.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct DevicePreference {
+    #[serde(default)]
+    pub sample_rate: Option<u32>,
+    #[serde(default)]
+    pub channels: Option<u16>,
+}
+
+/// Tunables for the FFT-based voice-activity detector `audio::run_mixing_loop`
+/// runs over the mixed mono stream. Off by default since trimming silence changes
+/// the recorded audio, which some users may not expect.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct VadConfig {
+    /// Master switch. When `false`, `run_mixing_loop` writes every sample as it
+    /// always has and never splits a segment early.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Continuous runs of detected silence longer than this are shortened down to
+    /// this length before being written, instead of being recorded verbatim.
+    #[serde(default = "default_max_silence_ms")]
+    pub max_silence_ms: u32,
+    /// When the segment timer is within this many seconds of `MAX_SEGMENT_DURATION_SECS`,
+    /// `run_mixing_loop` ends the segment on the next detected silence boundary
+    /// instead of waiting for the hard cap, so splits land on a pause rather than
+    /// mid-word.
+    #[serde(default = "default_prefer_split_window_secs")]
+    pub prefer_split_window_secs: u64,
+}
+
+fn default_max_silence_ms() -> u32 {
+    2_000
+}
+
+fn default_prefer_split_window_secs() -> u64 {
+    15
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        VadConfig {
+            enabled: false,
+            max_silence_ms: default_max_silence_ms(),
+            prefer_split_window_secs: default_prefer_split_window_secs(),
+        }
+    }
+}
+
+/// Bounds on how far the system-audio stream is allowed to run ahead of or behind
+/// the mic "master clock" in `audio::run_mixing_loop`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AudioBufferingConfig {
+    /// How many milliseconds of system audio to pre-buffer before mixing starts
+    /// drawing from it. Below this, `run_mixing_loop` treats the sys stream as
+    /// still warming up and mixes in silence rather than starving on a near-empty
+    /// buffer.
+    #[serde(default = "default_target_latency_ms")]
+    pub target_latency_ms: u64,
+    /// Backlog is allowed to grow this many milliseconds past `target_latency_ms`
+    /// before the oldest buffered frames are dropped to resync the sys stream back
+    /// onto the mic clock.
+    #[serde(default = "default_batch_ms")]
+    pub batch_ms: u64,
+}
+
+fn default_target_latency_ms() -> u64 {
+    200
+}
+
+fn default_batch_ms() -> u64 {
+    500
+}
+
+impl Default for AudioBufferingConfig {
+    fn default() -> Self {
+        AudioBufferingConfig {
+            target_latency_ms: default_target_latency_ms(),
+            batch_ms: default_batch_ms(),
+        }
+    }
+}
+
+/// Low-latency live delivery alongside the reliable batch uploader, used by
+/// `live_stream::LiveStreamSender`. Off by default: it's an additive path for
+/// listeners who want near-real-time audio, not a replacement for the archival
+/// upload `uploader::upload_segment` always performs.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LiveStreamConfig {
+    /// Master switch. When `false`, `LiveStreamSender::connect` returns `None` and
+    /// `run_segment_encoder_task` never attempts to open a QUIC connection.
+    #[serde(default)]
+    pub enabled: bool,
+    /// QUIC/WebTransport endpoint segments are streamed to, e.g.
+    /// "live.nojoin.example.com:4433". Required when `enabled` is `true`.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+}
+
+impl Default for LiveStreamConfig {
+    fn default() -> Self {
+        LiveStreamConfig {
+            enabled: false,
+            endpoint: None,
+        }
+    }
+}
+
+/// See `Config::channel`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReleaseChannel {
+    Stable,
+    Beta,
+}
+
+impl Default for ReleaseChannel {
+    fn default() -> Self {
+        ReleaseChannel::Stable
+    }
+}
+
+fn default_health_check_base_interval_secs() -> u64 {
+    5
+}
+
+fn default_health_check_max_interval_secs() -> u64 {
+    300
+}
+
+fn default_health_check_backoff_multiplier() -> f64 {
+    2.0
 }
 
 fn default_api_port() -> u16 {
@@ -37,6 +252,14 @@ fn default_local_port() -> u16 {
     DEFAULT_LOCAL_PORT
 }
 
+fn default_scoped_token_ttl_secs() -> u64 {
+    3600
+}
+
+fn default_transcription_model() -> String {
+    "base".to_string()
+}
+
 /// Legacy config format for migration from older versions.
 /// Fields may not all be used directly, but are needed for deserialization.
 #[derive(Deserialize)]
@@ -58,6 +281,16 @@ impl Config {
         format!("https://{}:{}", self.api_host, self.api_port)
     }
 
+    /// Directory for on-disk state that isn't `config.json` itself, e.g. the durable
+    /// upload queue. Lives alongside the config file so both respect the same
+    /// dev-override / portable-install rules.
+    pub fn data_dir() -> PathBuf {
+        Self::get_config_path()
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
     fn get_config_path() -> PathBuf {
         let config_name = "config.json";
         
@@ -136,6 +369,26 @@ impl Config {
             local_port: DEFAULT_LOCAL_PORT,
             input_device_name: legacy.input_device_name,
             output_device_name: legacy.output_device_name,
+            pushgateway_url: None,
+            scoped_token_ttl_secs: default_scoped_token_ttl_secs(),
+            access_token: None,
+            refresh_token: None,
+            expires_at: None,
+            pubkey: None,
+            channel: ReleaseChannel::default(),
+            health_check_base_interval_secs: default_health_check_base_interval_secs(),
+            health_check_max_interval_secs: default_health_check_max_interval_secs(),
+            health_check_backoff_multiplier: default_health_check_backoff_multiplier(),
+            dismissed_update_version: None,
+            transcription_enabled: false,
+            transcription_model: default_transcription_model(),
+            audio_buffering: AudioBufferingConfig::default(),
+            vad: VadConfig::default(),
+            input_device_preference: None,
+            output_device_preference: None,
+            segment_codec: crate::encoder::SegmentCodec::default(),
+            live_stream: LiveStreamConfig::default(),
+            dither_enabled: default_dither_enabled(),
         })
     }
 
@@ -207,6 +460,26 @@ impl Default for Config {
             local_port: DEFAULT_LOCAL_PORT,
             input_device_name: None,
             output_device_name: None,
+            pushgateway_url: None,
+            scoped_token_ttl_secs: default_scoped_token_ttl_secs(),
+            access_token: None,
+            refresh_token: None,
+            expires_at: None,
+            pubkey: None,
+            channel: ReleaseChannel::default(),
+            health_check_base_interval_secs: default_health_check_base_interval_secs(),
+            health_check_max_interval_secs: default_health_check_max_interval_secs(),
+            health_check_backoff_multiplier: default_health_check_backoff_multiplier(),
+            dismissed_update_version: None,
+            transcription_enabled: false,
+            transcription_model: default_transcription_model(),
+            audio_buffering: AudioBufferingConfig::default(),
+            vad: VadConfig::default(),
+            input_device_preference: None,
+            output_device_preference: None,
+            segment_codec: crate::encoder::SegmentCodec::default(),
+            live_stream: LiveStreamConfig::default(),
+            dither_enabled: default_dither_enabled(),
         }
     }
 }
@@ -0,0 +1,321 @@
+//! Pluggable segment file encoders. `audio::run_mixing_loop` feeds mixed mono f32
+//! frames to whichever [`SegmentEncoder`] `Config::segment_codec` selects instead of
+//! hard-coding `hound` i16 WAV output; `uploader` carries the resulting
+//! [`SegmentCodec`] alongside each segment so the backend knows how to decode it.
+
+use anyhow::Result;
+use rand::Rng;
+use std::path::{Path, PathBuf};
+
+/// Codec a recording segment is written with, selectable via `Config::segment_codec`.
+/// `Wav` stays the default (uncompressed, matches every segment recorded before this
+/// existed); `Flac` and `Opus` trade the frontend's device-picker-style opt-in for much
+/// smaller uploads.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SegmentCodec {
+    Wav,
+    Flac,
+    Opus,
+}
+
+impl Default for SegmentCodec {
+    fn default() -> Self {
+        SegmentCodec::Wav
+    }
+}
+
+impl SegmentCodec {
+    /// File extension `create_segment_encoder` writes and `uploader::upload_segment`
+    /// names the multipart part after, so the backend can tell codecs apart without
+    /// sniffing the payload.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SegmentCodec::Wav => "wav",
+            SegmentCodec::Flac => "flac",
+            SegmentCodec::Opus => "opus",
+        }
+    }
+}
+
+/// Sink for a segment's mixed mono audio. Implementations own whatever framing
+/// (headers, packetization) their codec needs; `run_mixing_loop` only ever calls
+/// `write_frame` with f32 samples in -1.0..=1.0 and `finalize` once the segment ends.
+pub trait SegmentEncoder {
+    /// Encodes one batch of mixed mono samples. Called repeatedly as audio arrives;
+    /// frame boundaries carry no meaning to the encoder beyond batching.
+    fn write_frame(&mut self, samples: &[f32]) -> Result<()>;
+
+    /// Flushes and closes the underlying file, returning its path. Consumes `self`
+    /// since most codecs (FLAC, Opus/Ogg) need to finalize a header or trailer that
+    /// can only be written once, the same way `hound::WavWriter::finalize` does.
+    fn finalize(self: Box<Self>) -> Result<PathBuf>;
+}
+
+/// Builds the segment file at `path` (extension should already match `codec`) and
+/// returns an encoder ready to receive `write_frame` calls at `sample_rate`, mono.
+/// `dither` selects triangular-PDF dither on the i16 quantization step `Wav`/`Flac`
+/// do (see `quantize_i16`); `Opus` encodes straight from f32 and ignores it.
+pub fn create_segment_encoder(
+    codec: SegmentCodec,
+    path: &Path,
+    sample_rate: u32,
+    dither: bool,
+) -> Result<Box<dyn SegmentEncoder>> {
+    match codec {
+        SegmentCodec::Wav => Ok(Box::new(WavSegmentEncoder::create(path, sample_rate, dither)?)),
+        SegmentCodec::Flac => Ok(Box::new(FlacSegmentEncoder::create(path, sample_rate, dither)?)),
+        SegmentCodec::Opus => Ok(Box::new(OpusSegmentEncoder::create(path, sample_rate)?)),
+    }
+}
+
+/// Quantizes one f32 sample (already limited to roughly -1.0..=1.0 by
+/// `audio::apply_soft_limiter`) to i16. When `dither` is set, adds triangular-PDF
+/// dither — the sum of two independent uniform values scaled to one LSB — before
+/// rounding, which trades a small, signal-independent noise floor for getting rid of
+/// the harmonic distortion plain rounding leaves on quiet passages. Rounds to the
+/// nearest integer either way rather than truncating, so quantization error doesn't
+/// carry a constant downward bias.
+fn quantize_i16(sample: f32, dither: bool) -> i16 {
+    let mut scaled = sample * i16::MAX as f32;
+    if dither {
+        let mut rng = rand::thread_rng();
+        let tpdf = rng.gen::<f32>() - rng.gen::<f32>();
+        scaled += tpdf;
+    }
+    scaled.round().clamp(i16::MIN as f32, i16::MAX as f32) as i16
+}
+
+/// Same i16 WAV output the mixing loop always wrote, just moved behind the
+/// [`SegmentEncoder`] trait so it's selectable alongside the compressed codecs.
+struct WavSegmentEncoder {
+    writer: hound::WavWriter<std::io::BufWriter<std::fs::File>>,
+    path: PathBuf,
+    dither: bool,
+}
+
+impl WavSegmentEncoder {
+    fn create(path: &Path, sample_rate: u32, dither: bool) -> Result<Self> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let writer = hound::WavWriter::create(path, spec)
+            .map_err(|e| anyhow::anyhow!("Failed to create wav writer: {}", e))?;
+        Ok(Self {
+            writer,
+            path: path.to_path_buf(),
+            dither,
+        })
+    }
+}
+
+impl SegmentEncoder for WavSegmentEncoder {
+    fn write_frame(&mut self, samples: &[f32]) -> Result<()> {
+        for &sample in samples {
+            let sample_i16 = quantize_i16(sample, self.dither);
+            self.writer
+                .write_sample(sample_i16)
+                .map_err(|e| anyhow::anyhow!("Failed to write wav sample: {}", e))?;
+        }
+        Ok(())
+    }
+
+    fn finalize(self: Box<Self>) -> Result<PathBuf> {
+        let path = self.path.clone();
+        self.writer
+            .finalize()
+            .map_err(|e| anyhow::anyhow!("Failed to finalize wav writer: {}", e))?;
+        Ok(path)
+    }
+}
+
+/// Lossless FLAC output, for users who want the bandwidth win of compression without
+/// giving up bit-exact-on-decode audio.
+struct FlacSegmentEncoder {
+    encoder: flac_bound::FlacEncoder<'static>,
+    path: PathBuf,
+    dither: bool,
+}
+
+impl FlacSegmentEncoder {
+    fn create(path: &Path, sample_rate: u32, dither: bool) -> Result<Self> {
+        let encoder = flac_bound::FlacEncoder::new()
+            .ok_or_else(|| anyhow::anyhow!("Failed to allocate FLAC encoder"))?
+            .channels(1)
+            .bits_per_sample(16)
+            .sample_rate(sample_rate)
+            .compression_level(5)
+            .init_file(path)
+            .map_err(|e| anyhow::anyhow!("Failed to init FLAC encoder: {:?}", e))?;
+        Ok(Self {
+            encoder,
+            path: path.to_path_buf(),
+            dither,
+        })
+    }
+}
+
+impl SegmentEncoder for FlacSegmentEncoder {
+    fn write_frame(&mut self, samples: &[f32]) -> Result<()> {
+        let ints: Vec<i32> = samples
+            .iter()
+            .map(|&s| quantize_i16(s, self.dither) as i32)
+            .collect();
+        self.encoder
+            .process_interleaved(&ints, ints.len() as u32)
+            .map_err(|e| anyhow::anyhow!("FLAC encode failed: {:?}", e))
+    }
+
+    fn finalize(self: Box<Self>) -> Result<PathBuf> {
+        let path = self.path.clone();
+        self.encoder
+            .finish()
+            .map_err(|(_, e)| anyhow::anyhow!("Failed to finalize FLAC encoder: {:?}", e))?;
+        Ok(path)
+    }
+}
+
+/// Linear-interpolation resampler, matching the one `companion/src/audio.rs` uses for
+/// its legacy Opus path. Good enough for voice at the ratios mic hardware actually
+/// shows up at (e.g. 44100 -> 48000); state carries across calls within a segment so
+/// chunk boundaries don't introduce clicks. Each segment's encoder starts a fresh one.
+struct LinearResampler {
+    ratio: f64, // output rate / input rate
+    pos: f64,
+    carry: f32,
+}
+
+impl LinearResampler {
+    fn new(ratio: f64) -> Self {
+        Self {
+            ratio,
+            pos: 0.0,
+            carry: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: &[f32], out: &mut Vec<f32>) {
+        if input.is_empty() {
+            return;
+        }
+        let n = input.len();
+        let step = 1.0 / self.ratio;
+        let at = |i: usize, carry: f32| -> f32 {
+            if i == 0 {
+                carry
+            } else {
+                input[i - 1]
+            }
+        };
+        while self.pos < n as f64 {
+            let idx = self.pos as usize;
+            let frac = (self.pos - idx as f64) as f32;
+            let s0 = at(idx, self.carry);
+            let s1 = at(idx + 1, self.carry);
+            out.push(s0 + (s1 - s0) * frac);
+            self.pos += step;
+        }
+        self.pos -= n as f64;
+        self.carry = input[n - 1];
+    }
+}
+
+/// Lossy Opus output tuned for speech, at a bitrate cheap enough to cut typical voice
+/// meeting uploads by an order of magnitude over raw WAV. Framed into an Ogg container
+/// (Opus packets packed into Ogg pages) so the file is self-contained on disk.
+struct OpusSegmentEncoder {
+    encoder: opus::Encoder,
+    ogg_writer: ogg::writing::PacketWriter<'static, std::fs::File>,
+    path: PathBuf,
+    /// Opus only accepts 8/12/16/24/48 kHz; `write_frame` resamples the mic's native
+    /// rate onto `OPUS_SAMPLE_RATE` before anything reaches `encoder`.
+    resampler: LinearResampler,
+    /// Opus only encodes fixed frame sizes (20ms here); samples that don't fill a
+    /// full frame yet are held here until the next `write_frame` call tops them up.
+    pending: Vec<f32>,
+    frame_size: usize,
+    granulepos: u64,
+}
+
+/// 20ms frames are the usual sweet spot for Opus's voice (`VoIP`) application mode:
+/// short enough for low latency, long enough to keep per-frame overhead down.
+const OPUS_FRAME_MS: u32 = 20;
+/// ~24-32 kbps covers clear speech; this sits in the middle of that range.
+const OPUS_BITRATE: i32 = 28_000;
+/// The rate every Opus segment is encoded at (Opus itself accepts only 8/12/16/24/48 kHz).
+/// Mic hardware routinely runs at other rates (44100 Hz
+/// is the common case), so every segment is resampled onto this one before encoding.
+const OPUS_SAMPLE_RATE: u32 = 48_000;
+
+impl OpusSegmentEncoder {
+    fn create(path: &Path, sample_rate: u32) -> Result<Self> {
+        let mut encoder = opus::Encoder::new(OPUS_SAMPLE_RATE, opus::Channels::Mono, opus::Application::Voip)
+            .map_err(|e| anyhow::anyhow!("Failed to create Opus encoder: {}", e))?;
+        encoder
+            .set_bitrate(opus::Bitrate::Bits(OPUS_BITRATE))
+            .map_err(|e| anyhow::anyhow!("Failed to set Opus bitrate: {}", e))?;
+
+        let file = std::fs::File::create(path)
+            .map_err(|e| anyhow::anyhow!("Failed to create opus file {:?}: {}", path, e))?;
+        let ogg_writer = ogg::writing::PacketWriter::new(file);
+
+        let frame_size = (OPUS_SAMPLE_RATE * OPUS_FRAME_MS / 1000) as usize;
+
+        Ok(Self {
+            encoder,
+            ogg_writer,
+            path: path.to_path_buf(),
+            resampler: LinearResampler::new(OPUS_SAMPLE_RATE as f64 / sample_rate as f64),
+            pending: Vec::with_capacity(frame_size),
+            frame_size,
+            granulepos: 0,
+        })
+    }
+}
+
+impl SegmentEncoder for OpusSegmentEncoder {
+    fn write_frame(&mut self, samples: &[f32]) -> Result<()> {
+        let mut resampled = Vec::new();
+        self.resampler.process(samples, &mut resampled);
+        self.pending.extend_from_slice(&resampled);
+
+        while self.pending.len() >= self.frame_size {
+            let frame: Vec<f32> = self.pending.drain(..self.frame_size).collect();
+            let packet = self
+                .encoder
+                .encode_vec_float(&frame, self.frame_size * 4)
+                .map_err(|e| anyhow::anyhow!("Opus encode failed: {}", e))?;
+            self.granulepos += self.frame_size as u64;
+            self.ogg_writer
+                .write_packet(
+                    packet,
+                    0,
+                    ogg::writing::PacketWriteEndInfo::NormalPacket,
+                    self.granulepos,
+                )
+                .map_err(|e| anyhow::anyhow!("Failed to write Ogg packet: {}", e))?;
+        }
+        Ok(())
+    }
+
+    fn finalize(mut self: Box<Self>) -> Result<PathBuf> {
+        // Always write a final page explicitly marked `EndStream`, even when
+        // `pending` is empty (an exact multiple of `frame_size`, or no samples at
+        // all) — otherwise the Ogg stream's last page is a `NormalPacket` and has
+        // no end-of-stream marker at all.
+        let mut frame = std::mem::take(&mut self.pending);
+        frame.resize(self.frame_size, 0.0);
+        let packet = self
+            .encoder
+            .encode_vec_float(&frame, self.frame_size * 4)
+            .map_err(|e| anyhow::anyhow!("Opus encode failed: {}", e))?;
+        self.granulepos += self.frame_size as u64;
+        self.ogg_writer
+            .write_packet(packet, 0, ogg::writing::PacketWriteEndInfo::EndStream, self.granulepos)
+            .map_err(|e| anyhow::anyhow!("Failed to write final Ogg packet: {}", e))?;
+        Ok(self.path)
+    }
+}
@@ -1,4 +1,8 @@
 #[cfg(target_os = "linux")]
+use crate::config::Config;
+#[cfg(target_os = "linux")]
+use crate::update_verify;
+#[cfg(target_os = "linux")]
 use log::error;
 #[cfg(target_os = "linux")]
 use notify_rust::{Notification, Timeout};
@@ -7,8 +11,14 @@ use tauri::AppHandle;
 #[cfg(target_os = "linux")]
 use tauri_plugin_updater::UpdaterExt;
 
+/// Minimum gap between progress-notification updates. notify-rust has to round-trip
+/// the D-Bus daemon on every update, so refreshing on every chunk would flood it;
+/// once a second is plenty for a human to track download progress by.
+#[cfg(target_os = "linux")]
+const PROGRESS_THROTTLE: std::time::Duration = std::time::Duration::from_secs(1);
+
 #[cfg(target_os = "linux")]
-pub fn show_update_notification(app: AppHandle, version: String) {
+pub fn show_update_notification(app: AppHandle, version: String, _url: String, config: Config) {
     let app_handle = app.clone();
 
     // notify-rust's wait_for_action blocks, so this is fine in the thread.
@@ -26,7 +36,7 @@ pub fn show_update_notification(app: AppHandle, version: String) {
         Ok(handle) => {
             handle.wait_for_action(move |action| {
                 if action == "update" {
-                    trigger_update(app_handle.clone());
+                    trigger_update(app_handle.clone(), config.clone());
                 }
             });
         }
@@ -37,7 +47,7 @@ pub fn show_update_notification(app: AppHandle, version: String) {
 }
 
 #[cfg(target_os = "linux")]
-fn trigger_update(app: AppHandle) {
+fn trigger_update(app: AppHandle, config: Config) {
     tauri::async_runtime::spawn(async move {
         let updater = match app.updater() {
             Ok(u) => u,
@@ -49,7 +59,90 @@ fn trigger_update(app: AppHandle) {
 
         match updater.check().await {
             Ok(Some(update)) => {
-                if let Err(e) = update.download_and_install(|_, _| {}, || {}).await {
+                // Download the raw artifact ourselves (instead of `download_and_install`)
+                // so it can be checked against the detached minisign signature before
+                // anything touches disk. Progress is surfaced by updating a single
+                // notification in place rather than re-showing one per chunk, which
+                // would flicker and spam the notification daemon.
+                let mut progress_handle: Option<notify_rust::NotificationHandle> = None;
+                let mut downloaded: u64 = 0;
+                let mut last_update = std::time::Instant::now() - PROGRESS_THROTTLE;
+                let artifact = match update
+                    .download(
+                        |chunk_len, total_len| {
+                            downloaded += chunk_len as u64;
+                            if last_update.elapsed() < PROGRESS_THROTTLE {
+                                return;
+                            }
+                            last_update = std::time::Instant::now();
+
+                            let body = match total_len {
+                                Some(total) if total > 0 => {
+                                    format!("Downloading update... {}%", (downloaded * 100 / total).min(100))
+                                }
+                                _ => format!("Downloading update... {} KB", downloaded / 1024),
+                            };
+
+                            match progress_handle.as_mut() {
+                                Some(handle) => {
+                                    handle.summary("Updating Nojoin Companion").body(&body);
+                                    handle.update();
+                                }
+                                None => {
+                                    if let Ok(handle) = Notification::new()
+                                        .summary("Updating Nojoin Companion")
+                                        .body(&body)
+                                        .icon("dialog-information")
+                                        .appname("Nojoin Companion")
+                                        .timeout(Timeout::Milliseconds(3000))
+                                        .show()
+                                    {
+                                        progress_handle = Some(handle);
+                                    }
+                                }
+                            }
+                        },
+                        || {},
+                    )
+                    .await
+                {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        error!("Failed to download update: {}", e);
+                        return;
+                    }
+                };
+                if let Some(handle) = progress_handle.take() {
+                    handle.close();
+                }
+
+                let sig_url = format!("{}.sig", update.download_url);
+                let sig_text = match reqwest::get(&sig_url).await.and_then(|r| r.error_for_status()) {
+                    Ok(resp) => match resp.text().await {
+                        Ok(text) => text,
+                        Err(e) => {
+                            error!("Failed to read update signature: {}", e);
+                            return;
+                        }
+                    },
+                    Err(e) => {
+                        error!("Failed to download update signature from {}: {}", sig_url, e);
+                        return;
+                    }
+                };
+
+                let pubkey = update_verify::resolve_pubkey(config.pubkey.as_ref());
+                if let Err(e) = update_verify::verify_artifact(&artifact, &sig_text, &pubkey) {
+                    error!("Update signature verification failed, refusing to install: {}", e);
+                    crate::notifications::show_notification(
+                        &app,
+                        "Update Failed",
+                        "The downloaded update's signature could not be verified. Installation was aborted.",
+                    );
+                    return;
+                }
+
+                if let Err(e) = update.install(artifact) {
                     error!("Failed to install update: {}", e);
                 } else {
                     app.restart();
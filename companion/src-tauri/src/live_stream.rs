@@ -0,0 +1,123 @@
+//! Low-latency live delivery of recording segments over QUIC, alongside (never instead
+//! of) the reliable batch uploader in `uploader`. Each segment is pushed on its own
+//! prioritized unidirectional stream: newer segments get higher priority, and an older
+//! segment that's still sitting unsent when a newer one arrives is cancelled outright
+//! rather than left to block behind it. A listener connected to a congested link gets
+//! near-real-time audio with occasional gaps instead of audio that's always complete
+//! but arbitrarily delayed.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::{Mutex as AsyncMutex, Notify};
+
+use crate::config::LiveStreamConfig;
+
+/// Holds the QUIC connection and a cancellation handle for whatever segment is still
+/// being sent. Constructed once at startup by `connect` and shared (via
+/// `AppState::live_stream_sender`) across every recording, the same way `UploadQueue` is.
+pub struct LiveStreamSender {
+    connection: quinn::Connection,
+    /// One entry per segment whose stream hasn't finished sending yet, keyed by
+    /// sequence number. The write itself stays local to the task running
+    /// `send_segment`; this only holds a [`Notify`] so a newer segment's call can
+    /// wake a still-writing older one up to cancel, without needing the write itself
+    /// to hold this lock.
+    in_flight: AsyncMutex<BTreeMap<i32, Arc<Notify>>>,
+}
+
+impl LiveStreamSender {
+    /// Dials `config.endpoint` and returns a sender ready for `send_segment`, or `None`
+    /// when live streaming is disabled. Errors (bad endpoint, handshake failure) are
+    /// logged and also turned into `None` rather than failing startup — live delivery
+    /// is an addition to recording, not a dependency of it.
+    pub async fn connect(config: &LiveStreamConfig) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        let Some(endpoint_addr) = config.endpoint.as_deref() else {
+            log::warn!("live_stream.enabled is true but no endpoint is configured");
+            return None;
+        };
+
+        match Self::dial(endpoint_addr).await {
+            Ok(connection) => Some(Self {
+                connection,
+                in_flight: AsyncMutex::new(BTreeMap::new()),
+            }),
+            Err(e) => {
+                log::error!("Failed to connect live stream endpoint {}: {}", endpoint_addr, e);
+                None
+            }
+        }
+    }
+
+    async fn dial(endpoint_addr: &str) -> anyhow::Result<quinn::Connection> {
+        let remote: std::net::SocketAddr = tokio::net::lookup_host(endpoint_addr)
+            .await?
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("could not resolve {}", endpoint_addr))?;
+        let server_name = endpoint_addr
+            .rsplit_once(':')
+            .map(|(host, _)| host)
+            .unwrap_or(endpoint_addr);
+
+        let client_config = quinn::ClientConfig::with_native_roots();
+        let mut endpoint = quinn::Endpoint::client("0.0.0.0:0".parse()?)?;
+        endpoint.set_default_client_config(client_config);
+
+        let connection = endpoint.connect(remote, server_name)?.await?;
+        Ok(connection)
+    }
+
+    /// Streams `data` (already-encoded segment bytes) on its own stream, prioritized by
+    /// `sequence` so newer segments overtake older ones still waiting to send, and
+    /// cancels every older segment that hasn't finished sending yet rather than letting
+    /// it head-of-line-block this one.
+    pub async fn send_segment(&self, sequence: i32, data: Vec<u8>) {
+        let mut stream = match self.connection.open_uni().await {
+            Ok(stream) => stream,
+            Err(e) => {
+                log::warn!("Failed to open live stream for segment {}: {}", sequence, e);
+                return;
+            }
+        };
+        if let Err(e) = stream.set_priority(sequence) {
+            log::warn!("Failed to set live stream priority for segment {}: {}", sequence, e);
+        }
+
+        let cancel = Arc::new(Notify::new());
+        {
+            // Register this segment and wake every older one that's still sitting
+            // unsent so it cancels itself. Held only for this bookkeeping — never
+            // across the write below — so a slow or congested write can't delay the
+            // very cancellation that's supposed to pre-empt it.
+            let mut in_flight = self.in_flight.lock().await;
+            let stale: Vec<i32> = in_flight.range(..sequence).map(|(&seq, _)| seq).collect();
+            for seq in stale {
+                if let Some(old_cancel) = in_flight.remove(&seq) {
+                    old_cancel.notify_one();
+                }
+            }
+            in_flight.insert(sequence, cancel.clone());
+        }
+
+        // `stream` stays owned by this task the whole time; a newer `send_segment`
+        // call only ever reaches it by waking `cancel`, never by touching the stream
+        // directly, so there's no aliasing to worry about here.
+        let write_result = tokio::select! {
+            result = async {
+                stream.write_all(&data).await?;
+                stream.finish().await
+            } => result,
+            _ = cancel.notified() => {
+                let _ = stream.reset(quinn::VarInt::from_u32(0));
+                Ok(())
+            }
+        };
+        self.in_flight.lock().await.remove(&sequence);
+
+        if let Err(e) = write_result {
+            log::warn!("Failed to write live stream segment {}: {}", sequence, e);
+        }
+    }
+}
@@ -4,9 +4,10 @@
 )]
 
 use log::{error, info};
+use rand::Rng;
 use reqwest;
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
@@ -18,19 +19,30 @@ use tauri::{
 
 mod audio;
 mod config;
+mod encoder;
 mod linux_notifications;
+mod live_stream;
 mod mac_notifications;
+mod manifest;
+mod metrics;
 mod notifications;
 mod server;
 mod state;
+mod transcribe;
+mod update_prompt;
+mod update_verify;
+mod updater;
 mod uploader;
+mod vad;
+#[cfg(windows)]
+mod win_audio;
 mod win_notifications;
 
 #[cfg(target_os = "macos")]
 mod mac_sc;
 
 use config::Config;
-use state::{AppState, AppStatus};
+use state::{AppState, AppStatus, AudioCommand, AudioStatus};
 use tauri_plugin_autostart::{MacosLauncher, ManagerExt};
 
 // Define SharedAppState at module level so it's visible to commands
@@ -47,9 +59,10 @@ fn get_config(state: tauri::State<SharedAppState>) -> Config {
 fn save_config(
     state: tauri::State<SharedAppState>,
     server_url: String,
+    channel: Option<String>,
 ) -> Result<(), String> {
     let mut config = state.0.config.lock().unwrap();
-    
+
     // Parse URL
     // If it doesn't start with http:// or https://, assume https://
     let url_str = if !server_url.contains("://") {
@@ -64,6 +77,13 @@ fn save_config(
     config.api_host = url.host_str().unwrap_or("localhost").to_string();
     config.api_port = url.port().unwrap_or_else(|| if config.api_protocol == "http" { 80 } else { 443 });
 
+    if let Some(channel) = channel {
+        config.channel = match channel.as_str() {
+            "beta" => config::ReleaseChannel::Beta,
+            _ => config::ReleaseChannel::Stable,
+        };
+    }
+
     config.save().map_err(|e| e.to_string())?;
     Ok(())
 }
@@ -73,73 +93,44 @@ fn close_update_prompt(window: tauri::Window) {
     let _ = window.close();
 }
 
-#[derive(serde::Deserialize)]
-struct GitHubRelease {
-    tag_name: String,
-    html_url: String,
+/// Returns the current status immediately, so a newly-opened window isn't left
+/// blank until the next `status://update` transition.
+#[tauri::command]
+fn status_snapshot(state: tauri::State<SharedAppState>) -> server::StatusSnapshot {
+    server::build_status_snapshot(&state.0)
 }
 
-async fn check_github_release(current_version: &str) -> Result<Option<(String, String)>, String> {
-    let client = reqwest::Client::builder()
-        .user_agent("Nojoin-Companion")
-        .build()
-        .map_err(|e| e.to_string())?;
-
-    let resp = client
-        .get("https://api.github.com/repos/Valtora/Nojoin/releases/latest")
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    if resp.status().is_success() {
-        let release: GitHubRelease = resp.json().await.map_err(|e| e.to_string())?;
-        // tag_name is usually like "companion-v0.1.4" or "v0.1.4"
-        // We need to parse it.
-        let version_str = release.tag_name.trim_start_matches("companion-v").trim_start_matches('v');
-        
-        // Simple version comparison (lexicographical might fail for 0.1.10 vs 0.1.9, but semver crate is better if available)
-        // Since we don't have semver crate in Cargo.toml, let's try to use a simple split check or just string compare if format is consistent.
-        // For robustness, let's assume if strings are different, it's an update (or downgrade).
-        // But we only want to notify on NEWER version.
-        // Let's just check inequality for now, or try to parse.
-        
-        if version_str != current_version {
-             // It's different. Is it newer?
-             // Let's just return it if it's different for now, user can decide.
-             // Or better, let's try to parse major.minor.patch
-             return Ok(Some((version_str.to_string(), release.html_url)));
-        }
-        Ok(None)
-    } else {
-        Err(format!("Failed to fetch releases: {}", resp.status()))
-    }
+/// Lists every input/output device `cpal` currently sees, with its supported
+/// sample rate/channel/format ranges, for the frontend's device picker.
+#[tauri::command]
+fn list_audio_devices() -> audio::AudioDeviceList {
+    audio::enumerate_audio_devices()
 }
 
 async fn check_and_prompt_update(app: &tauri::AppHandle, silent: bool) {
     let current_version = app.package_info().version.to_string();
-    
-    match check_github_release(&current_version).await {
-        Ok(Some((version, url))) => {
+    let channel = {
+        let state_wrapper = app.state::<SharedAppState>();
+        state_wrapper.0.config.lock().unwrap().channel.clone()
+    };
+
+    match updater::check_latest_release(&current_version, &channel).await {
+        Ok(Some(release)) => {
             let state_wrapper = app.state::<SharedAppState>();
-            let state = &state_wrapper.0;
+            let state = state_wrapper.0.clone();
 
+            let version = updater::release_version(&release.tag_name);
             state.update_available.store(true, Ordering::SeqCst);
             *state.latest_version.lock().unwrap() = Some(version.clone());
-            *state.latest_update_url.lock().unwrap() = Some(url.clone());
-
-            #[cfg(windows)]
-            {
-                win_notifications::show_update_notification(app.clone(), version, url);
-            }
-
-            #[cfg(target_os = "macos")]
-            {
-                mac_notifications::show_update_notification(app.clone(), version.clone(), url);
-            }
-
-            #[cfg(target_os = "linux")]
-            {
-                linux_notifications::show_update_notification(app.clone(), version, url);
+            *state.latest_update_url.lock().unwrap() = Some(release.html_url.clone());
+            let dismissed = state.config.lock().unwrap().dismissed_update_version.clone();
+            server::publish_status(&state, app);
+
+            // A manual "Check for Updates" click always prompts, even if this
+            // version was previously dismissed; a silent/periodic check respects
+            // a standing "Remind Me Later" until a newer release ships.
+            if !silent || dismissed.as_deref() != Some(version.as_str()) {
+                update_prompt::prompt_install(app.clone(), state, release, version);
             }
         }
         Ok(None) => {
@@ -155,6 +146,51 @@ async fn check_and_prompt_update(app: &tauri::AppHandle, silent: bool) {
     }
 }
 
+/// Subscribes to `AppState::audio_status_tx` for the lifetime of the app and keeps
+/// the Pause/Resume tray items in sync with what the audio loop is actually doing,
+/// rather than a timer re-locking `status` and possibly reading a stale value mid-transition.
+async fn watch_audio_status(state: Arc<AppState>) {
+    let mut rx = state.audio_status_tx.subscribe();
+    // A late subscriber (this task starts slightly after the audio loop thread) would
+    // otherwise see nothing until the next transition; ask the audio loop to replay
+    // whatever it currently thinks its state is.
+    let _ = state.audio_command_tx.send(AudioCommand::Query);
+
+    loop {
+        match rx.recv().await {
+            Ok(AudioStatus::Recording) => {
+                if let Some(item) = state.tray_pause_item.lock().unwrap().as_ref() {
+                    let _ = item.set_enabled(true);
+                }
+                if let Some(item) = state.tray_resume_item.lock().unwrap().as_ref() {
+                    let _ = item.set_enabled(false);
+                }
+            }
+            Ok(AudioStatus::Paused) => {
+                if let Some(item) = state.tray_pause_item.lock().unwrap().as_ref() {
+                    let _ = item.set_enabled(false);
+                }
+                if let Some(item) = state.tray_resume_item.lock().unwrap().as_ref() {
+                    let _ = item.set_enabled(true);
+                }
+            }
+            Ok(AudioStatus::Stopped) | Ok(AudioStatus::Error(_)) => {
+                if let Some(item) = state.tray_pause_item.lock().unwrap().as_ref() {
+                    let _ = item.set_enabled(false);
+                }
+                if let Some(item) = state.tray_resume_item.lock().unwrap().as_ref() {
+                    let _ = item.set_enabled(false);
+                }
+            }
+            Ok(AudioStatus::LevelUpdate { .. }) => {}
+            // Lagged subscribers just skip the missed messages; the next transition
+            // (or another Query) will bring the tray back in sync.
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
 fn get_log_path() -> PathBuf {
     // In Tauri, we might want to use the app data directory, but for now let's stick to exe dir or current dir
     std::env::current_exe()
@@ -202,13 +238,22 @@ fn main() {
     }
 
     builder
-        .invoke_handler(tauri::generate_handler![get_config, save_config, close_update_prompt])
+        .invoke_handler(tauri::generate_handler![
+            get_config,
+            save_config,
+            close_update_prompt,
+            status_snapshot,
+            list_audio_devices
+        ])
         .setup(|app| {
             // Permission checks are handled by the frontend or implicitly by the OS in v2
 
             let (audio_tx, audio_rx) = crossbeam_channel::unbounded();
             let config = Config::load();
-            
+            let live_stream_config = config.live_stream.clone();
+            let upload_queue = uploader::UploadQueue::open(&Config::data_dir())
+                .expect("Failed to open upload queue store");
+
             let state = Arc::new(AppState {
                 status: Mutex::new(AppStatus::Idle),
                 current_recording_id: Mutex::new(None),
@@ -219,6 +264,20 @@ fn main() {
                 accumulated_duration: Mutex::new(Duration::new(0, 0)),
                 input_level: AtomicU32::new(0),
                 output_level: AtomicU32::new(0),
+                sys_buffer_fill_ms: AtomicU32::new(0),
+                speech_energy: AtomicU32::new(0),
+                recordings_started_total: AtomicU64::new(0),
+                recordings_stopped_total: AtomicU64::new(0),
+                recordings_failed_total: AtomicU64::new(0),
+                uploaded_bytes_total: AtomicU64::new(0),
+                uploaded_chunks_total: AtomicU64::new(0),
+                upload_retries_total: AtomicU64::new(0),
+                reconnect_attempts_total: AtomicU64::new(0),
+                upload_queue,
+                pending_pairs: Mutex::new(std::collections::HashMap::new()),
+                scoped_tokens: Mutex::new(std::collections::HashMap::new()),
+                ws_tx: tokio::sync::broadcast::channel(64).0,
+                audio_status_tx: tokio::sync::broadcast::channel(64).0,
                 web_url: Mutex::new(None),
                 is_backend_connected: AtomicBool::new(false),
                 update_available: AtomicBool::new(false),
@@ -228,10 +287,22 @@ fn main() {
                 tray_run_on_startup_item: Mutex::new(None),
                 tray_open_web_item: Mutex::new(None),
                 tray_icon: Mutex::new(None),
+                tray_pause_item: Mutex::new(None),
+                tray_resume_item: Mutex::new(None),
+                live_stream_sender: Mutex::new(None),
             });
 
             app.manage(SharedAppState(state.clone()));
 
+            // Connecting is best-effort and shouldn't delay startup; `send_segment`
+            // callers already treat a `None` sender as "live streaming unavailable".
+            let state_live_stream = state.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Some(sender) = live_stream::LiveStreamSender::connect(&live_stream_config).await {
+                    *state_live_stream.live_stream_sender.lock().unwrap() = Some(Arc::new(sender));
+                }
+            });
+
             // Create Menu Items
             let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
             let about = MenuItem::with_id(app, "about", "About", true, None::<&str>)?;
@@ -249,15 +320,24 @@ fn main() {
             // Enable status item so it's not greyed out, but we won't attach an action to it
             let status_item = MenuItem::with_id(app, "status", "Status: Waiting for connection...", true, None::<&str>)?;
 
+            // Disabled until `watch_audio_status` sees an `AudioStatus::Recording`/`Paused`
+            // message; there's nothing to pause/resume before a recording has started.
+            let pause_recording = MenuItem::with_id(app, "pause_recording", "Pause Recording", false, None::<&str>)?;
+            let resume_recording = MenuItem::with_id(app, "resume_recording", "Resume Recording", false, None::<&str>)?;
+
             // Store items in state
             *state.tray_status_item.lock().unwrap() = Some(status_item.clone());
             *state.tray_run_on_startup_item.lock().unwrap() = Some(run_on_startup.clone());
             *state.tray_open_web_item.lock().unwrap() = Some(open_web.clone());
+            *state.tray_pause_item.lock().unwrap() = Some(pause_recording.clone());
+            *state.tray_resume_item.lock().unwrap() = Some(resume_recording.clone());
 
             let menu = Menu::with_items(app, &[
                 &status_item,
                 &PredefinedMenuItem::separator(app)?,
                 &open_web,
+                &pause_recording,
+                &resume_recording,
                 &settings,
                 &run_on_startup,
                 &check_updates,
@@ -313,10 +393,18 @@ fn main() {
                                 notifications::show_notification(app, "Error", "Backend URL not found.");
                             }
                         }
+                        "pause_recording" => {
+                            let state_wrapper = app.state::<SharedAppState>();
+                            let _ = state_wrapper.0.audio_command_tx.send(AudioCommand::Pause);
+                        }
+                        "resume_recording" => {
+                            let state_wrapper = app.state::<SharedAppState>();
+                            let _ = state_wrapper.0.audio_command_tx.send(AudioCommand::Resume);
+                        }
                         "about" => {
                              notifications::show_notification(
                                  app,
-                                 "About Nojoin Companion", 
+                                 "About Nojoin Companion",
                                  &format!("This is the Nojoin Companion App that let's Nojoin listen in on your meetings.\n\nVersion {}", app.package_info().version)
                              );
                         }
@@ -405,22 +493,57 @@ fn main() {
                 audio::run_audio_loop(state_audio, audio_rx, app_handle_audio);
             });
 
+            // Drives the pause/resume tray items purely from `AudioStatus` messages
+            // published by the audio loop, instead of polling `state.status` on a timer.
+            let state_watch = state.clone();
+            tauri::async_runtime::spawn(async move {
+                watch_audio_status(state_watch).await;
+            });
+
             let state_server = state.clone();
             let app_handle = app.handle().clone();
-            
+
             thread::spawn(move || {
                 let rt = tokio::runtime::Runtime::new().unwrap();
-                
+
+                // Pushgateway export loop (no-op while Config.pushgateway_url is unset)
+                let state_metrics = state_server.clone();
+                rt.spawn(async move {
+                    metrics::run_pushgateway_loop(state_metrics).await;
+                });
+
+                // Durable upload queue worker; also replays anything left in the sled
+                // store from a previous run, so there's no separate startup-recovery step
+                let state_queue = state_server.clone();
+                rt.spawn(async move {
+                    uploader::run_queue_worker(state_queue).await;
+                });
+
                 // Health Check & Status Update Loop
                 let state_fetch = state_server.clone();
-                
+                let app_handle_fetch = app_handle.clone();
+
                 rt.spawn(async move {
                     let client = reqwest::Client::builder()
                         .danger_accept_invalid_certs(true)
                         .timeout(Duration::from_secs(5))
                         .build()
                         .unwrap_or_default();
-                    
+
+                    // Tracks the previously-observed state so `status://update` only
+                    // fires on an actual transition, not every poll.
+                    let mut last_connected = state_fetch.is_backend_connected.load(Ordering::SeqCst);
+                    let mut last_status = state_fetch.status.lock().unwrap().clone();
+
+                    // Current poll interval, in seconds. Resets to the configured base
+                    // the moment a request succeeds; backs off toward the configured
+                    // cap each time one fails, so an idle/unreachable backend doesn't
+                    // get hammered or fill the log with retries all day.
+                    let mut interval_secs = {
+                        let config = state_fetch.config.lock().unwrap();
+                        config.health_check_base_interval_secs as f64
+                    };
+
                     loop {
                         // 1. Perform Health Check
                         let api_url = {
@@ -439,6 +562,7 @@ fn main() {
                                     }
                                 } else {
                                     state_fetch.is_backend_connected.store(false, Ordering::SeqCst);
+                                    state_fetch.reconnect_attempts_total.fetch_add(1, Ordering::Relaxed);
                                     let mut status = state_fetch.status.lock().unwrap();
                                     if *status == AppStatus::Idle {
                                         *status = AppStatus::BackendOffline;
@@ -447,6 +571,7 @@ fn main() {
                             }
                             Err(_) => {
                                 state_fetch.is_backend_connected.store(false, Ordering::SeqCst);
+                                state_fetch.reconnect_attempts_total.fetch_add(1, Ordering::Relaxed);
                                 let mut status = state_fetch.status.lock().unwrap();
                                 if *status == AppStatus::Idle {
                                     *status = AppStatus::BackendOffline;
@@ -454,6 +579,29 @@ fn main() {
                             }
                         }
 
+                        let connected_now = state_fetch.is_backend_connected.load(Ordering::SeqCst);
+                        let status_now = state_fetch.status.lock().unwrap().clone();
+                        if connected_now != last_connected || status_now != last_status {
+                            last_connected = connected_now;
+                            last_status = status_now;
+                            server::publish_status(&state_fetch, &app_handle_fetch);
+                        }
+
+                        let (base_secs, max_secs, multiplier) = {
+                            let config = state_fetch.config.lock().unwrap();
+                            (
+                                config.health_check_base_interval_secs as f64,
+                                config.health_check_max_interval_secs as f64,
+                                config.health_check_backoff_multiplier,
+                            )
+                        };
+                        interval_secs = if connected_now {
+                            base_secs
+                        } else {
+                            (interval_secs * multiplier).min(max_secs)
+                        };
+                        let jitter_secs: f64 = rand::thread_rng().gen_range(0.0..1.0);
+
                         // 2. Update Tray Icon Text
                         if let Ok(status) = state_fetch.status.try_lock() {
                              let status_text = if !state_fetch.is_authenticated() {
@@ -483,7 +631,7 @@ fn main() {
                              }
                         }
 
-                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        tokio::time::sleep(Duration::from_secs_f64(interval_secs + jitter_secs)).await;
                     }
                 });
 
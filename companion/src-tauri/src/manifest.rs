@@ -0,0 +1,78 @@
+//! Rolling HLS-style playlist for a recording in progress. Every segment is its own
+//! independent, fully self-contained file (see `encoder`), so without a manifest a
+//! listener has no way to discover them, their order, or their durations until
+//! `uploader::finalize_recording` closes the recording out. `run_mixing_loop` keeps one
+//! [`LiveManifest`] per recording, appends to it as each segment finishes encoding, and
+//! re-uploads the rendered playlist through `uploader::upload_manifest` so a player can
+//! follow along and seek within whatever's already landed.
+
+use std::collections::BTreeMap;
+
+/// One segment already available for playback.
+struct ManifestEntry {
+    uri: String,
+    duration_secs: f64,
+}
+
+/// Accumulates playlist state for one recording. Not reset between segments — each
+/// call to [`LiveManifest::push_segment`] just appends, since every segment recorded so
+/// far stays valid and playable for the life of the recording.
+pub struct LiveManifest {
+    /// `#EXT-X-MEDIA-SEQUENCE`: the sequence number of the first segment this playlist
+    /// ever lists. Fixed at construction since `run_mixing_loop` never drops old
+    /// entries — the whole recording is always listed, not a sliding window.
+    media_sequence: i32,
+    /// `#EXT-X-TARGETDURATION`: the longest segment duration seen so far, rounded up.
+    /// Per the HLS spec this must be an upper bound on every segment's actual
+    /// duration, so it can only grow as longer segments complete.
+    target_duration_secs: u64,
+    /// `#EXT-X-MAP`, for compressed codecs whose segments expect a shared header
+    /// rather than carrying one each. `None` for `SegmentCodec::Wav`, where every
+    /// segment is already a complete, independent file.
+    init_uri: Option<String>,
+    /// Keyed (and therefore ordered) by sequence number rather than a `Vec` appended
+    /// to as each segment's task finishes, since `run_segment_encoder_task`'s encode
+    /// and upload work runs per segment without waiting on earlier segments —
+    /// `push_segment` calls routinely arrive out of sequence order.
+    entries: BTreeMap<i32, ManifestEntry>,
+}
+
+impl LiveManifest {
+    pub fn new(media_sequence: i32) -> Self {
+        LiveManifest {
+            media_sequence,
+            target_duration_secs: 1,
+            init_uri: None,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    pub fn set_init_uri(&mut self, uri: String) {
+        self.init_uri = Some(uri);
+    }
+
+    pub fn push_segment(&mut self, sequence: i32, uri: String, duration_secs: f64) {
+        self.target_duration_secs = self.target_duration_secs.max(duration_secs.ceil() as u64);
+        self.entries.insert(sequence, ManifestEntry { uri, duration_secs });
+    }
+
+    /// Renders the current playlist. No `#EXT-X-ENDLIST` tag is emitted since the
+    /// recording is still in progress; nothing currently adds one once it stops,
+    /// since an in-progress manifest is the only case this exists to cover.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str("#EXTM3U\n");
+        out.push_str("#EXT-X-VERSION:7\n");
+        out.push_str(&format!("#EXT-X-TARGETDURATION:{}\n", self.target_duration_secs));
+        out.push_str(&format!("#EXT-X-MEDIA-SEQUENCE:{}\n", self.media_sequence));
+        if let Some(init_uri) = &self.init_uri {
+            out.push_str(&format!("#EXT-X-MAP:URI=\"{}\"\n", init_uri));
+        }
+        for entry in self.entries.values() {
+            out.push_str(&format!("#EXTINF:{:.3},\n", entry.duration_secs));
+            out.push_str(&entry.uri);
+            out.push('\n');
+        }
+        out
+    }
+}
@@ -0,0 +1,146 @@
+//! Prometheus text-exposition rendering and optional Pushgateway export for `AppState`.
+
+use crate::state::{AppState, AppStatus};
+use log::warn;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Render the current `AppState` as Prometheus text exposition format.
+pub fn render(state: &AppState) -> String {
+    let mut out = String::new();
+
+    let status = state.status.lock().unwrap().clone();
+    let duration = {
+        let acc = *state.accumulated_duration.lock().unwrap();
+        let start = *state.recording_start_time.lock().unwrap();
+        match status {
+            AppStatus::Recording => start
+                .and_then(|s| s.elapsed().ok())
+                .map(|e| acc + e)
+                .unwrap_or(acc),
+            _ => acc,
+        }
+    };
+
+    out.push_str("# HELP nojoin_status Current companion status (1 for the active variant, 0 otherwise)\n");
+    out.push_str("# TYPE nojoin_status gauge\n");
+    for variant in ["idle", "recording", "paused", "uploading", "backend_offline", "error"] {
+        let value = match (&status, variant) {
+            (AppStatus::Idle, "idle") => 1,
+            (AppStatus::Recording, "recording") => 1,
+            (AppStatus::Paused, "paused") => 1,
+            (AppStatus::Uploading, "uploading") => 1,
+            (AppStatus::BackendOffline, "backend_offline") => 1,
+            (AppStatus::Error(_), "error") => 1,
+            _ => 0,
+        };
+        out.push_str(&format!("nojoin_status{{state=\"{}\"}} {}\n", variant, value));
+    }
+
+    out.push_str("# HELP nojoin_recording_duration_seconds Duration of the in-progress recording\n");
+    out.push_str("# TYPE nojoin_recording_duration_seconds gauge\n");
+    out.push_str(&format!("nojoin_recording_duration_seconds {}\n", duration.as_secs()));
+
+    out.push_str("# HELP nojoin_input_level Current mic input level (0-100)\n");
+    out.push_str("# TYPE nojoin_input_level gauge\n");
+    out.push_str(&format!("nojoin_input_level {}\n", state.input_level.load(Ordering::Relaxed)));
+
+    out.push_str("# HELP nojoin_output_level Current system output level (0-100)\n");
+    out.push_str("# TYPE nojoin_output_level gauge\n");
+    out.push_str(&format!("nojoin_output_level {}\n", state.output_level.load(Ordering::Relaxed)));
+
+    out.push_str("# HELP nojoin_sys_buffer_fill_ms Current fill level of the system-audio ring buffer in run_mixing_loop\n");
+    out.push_str("# TYPE nojoin_sys_buffer_fill_ms gauge\n");
+    out.push_str(&format!(
+        "nojoin_sys_buffer_fill_ms {}\n",
+        state.sys_buffer_fill_ms.load(Ordering::Relaxed)
+    ));
+
+    out.push_str("# HELP nojoin_speech_energy Current speech-band energy from the voice-activity detector (0-100), 0 when vad.enabled is false\n");
+    out.push_str("# TYPE nojoin_speech_energy gauge\n");
+    out.push_str(&format!(
+        "nojoin_speech_energy {}\n",
+        state.speech_energy.load(Ordering::Relaxed)
+    ));
+
+    out.push_str("# HELP nojoin_recordings_started_total Recordings started\n");
+    out.push_str("# TYPE nojoin_recordings_started_total counter\n");
+    out.push_str(&format!(
+        "nojoin_recordings_started_total {}\n",
+        state.recordings_started_total.load(Ordering::Relaxed)
+    ));
+
+    out.push_str("# HELP nojoin_recordings_stopped_total Recordings stopped\n");
+    out.push_str("# TYPE nojoin_recordings_stopped_total counter\n");
+    out.push_str(&format!(
+        "nojoin_recordings_stopped_total {}\n",
+        state.recordings_stopped_total.load(Ordering::Relaxed)
+    ));
+
+    out.push_str("# HELP nojoin_recordings_failed_total Recordings that ended in AppStatus::Error\n");
+    out.push_str("# TYPE nojoin_recordings_failed_total counter\n");
+    out.push_str(&format!(
+        "nojoin_recordings_failed_total {}\n",
+        state.recordings_failed_total.load(Ordering::Relaxed)
+    ));
+
+    out.push_str("# HELP nojoin_uploaded_bytes_total Bytes of segment data uploaded to the backend\n");
+    out.push_str("# TYPE nojoin_uploaded_bytes_total counter\n");
+    out.push_str(&format!(
+        "nojoin_uploaded_bytes_total {}\n",
+        state.uploaded_bytes_total.load(Ordering::Relaxed)
+    ));
+
+    out.push_str("# HELP nojoin_uploaded_chunks_total Segment chunks uploaded to the backend\n");
+    out.push_str("# TYPE nojoin_uploaded_chunks_total counter\n");
+    out.push_str(&format!(
+        "nojoin_uploaded_chunks_total {}\n",
+        state.uploaded_chunks_total.load(Ordering::Relaxed)
+    ));
+
+    out.push_str("# HELP nojoin_upload_retries_total Upload attempts beyond the first for a chunk\n");
+    out.push_str("# TYPE nojoin_upload_retries_total counter\n");
+    out.push_str(&format!(
+        "nojoin_upload_retries_total {}\n",
+        state.upload_retries_total.load(Ordering::Relaxed)
+    ));
+
+    out.push_str("# HELP nojoin_backend_connected Whether the last health check reached the backend\n");
+    out.push_str("# TYPE nojoin_backend_connected gauge\n");
+    out.push_str(&format!(
+        "nojoin_backend_connected {}\n",
+        state.is_backend_connected.load(Ordering::SeqCst) as u8
+    ));
+
+    out.push_str("# HELP nojoin_reconnect_attempts_total Health checks that found the backend unreachable\n");
+    out.push_str("# TYPE nojoin_reconnect_attempts_total counter\n");
+    out.push_str(&format!(
+        "nojoin_reconnect_attempts_total {}\n",
+        state.reconnect_attempts_total.load(Ordering::Relaxed)
+    ));
+
+    out
+}
+
+/// Periodically push the same series rendered by [`render`] to a Pushgateway, when configured.
+pub async fn run_pushgateway_loop(state: Arc<AppState>) {
+    let client = reqwest::Client::new();
+
+    loop {
+        let gateway_url = {
+            let config = state.config.lock().unwrap();
+            config.pushgateway_url.clone()
+        };
+
+        if let Some(gateway_url) = gateway_url {
+            let body = render(&state);
+            let url = format!("{}/metrics/job/nojoin_companion", gateway_url.trim_end_matches('/'));
+            if let Err(e) = client.put(&url).body(body).send().await {
+                warn!("Failed to push metrics to Pushgateway at {}: {}", gateway_url, e);
+            }
+        }
+
+        tokio::time::sleep(Duration::from_secs(30)).await;
+    }
+}
@@ -1,18 +1,28 @@
+use crate::audio;
+use crate::metrics;
 use crate::notifications;
-use crate::state::{AppState, AppStatus, AudioCommand};
+use crate::state::{
+    AppState, AppStatus, AudioCommand, AudioStatus, PendingPair, ScopedToken, TokenScope,
+    PAIRING_TTL_SECS,
+};
 use crate::uploader;
 use axum::debug_handler;
 use axum::{
-    extract::State,
-    http::StatusCode,
+    body::Bytes,
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    extract::{Request, State},
+    http::{header, HeaderMap, Method, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
-use cpal::traits::{DeviceTrait, HostTrait};
 use log::{error, info};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tauri::Manager;
 use tower_http::cors::CorsLayer;
+use uuid::Uuid;
 
 #[derive(Clone)]
 pub struct ServerContext {
@@ -20,6 +30,24 @@ pub struct ServerContext {
     pub app_handle: tauri::AppHandle,
 }
 
+/// Uniform response envelope for every JSON route, so clients switch on `type` instead
+/// of inspecting HTTP status codes and ad-hoc message strings. `Failure` is for expected,
+/// recoverable conditions (e.g. "Already recording"); `Fatal` is for unexpected ones
+/// (e.g. a config save failing).
+#[derive(serde::Serialize)]
+#[serde(tag = "type", content = "content")]
+enum ApiResponse<T> {
+    Success(T),
+    Failure(String),
+    Fatal(String),
+}
+
+impl<T: serde::Serialize> IntoResponse for ApiResponse<T> {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
+
 pub async fn start_server(state: Arc<AppState>, app_handle: tauri::AppHandle) {
     let local_port = {
         let config = state.config.lock().unwrap();
@@ -27,10 +55,30 @@ pub async fn start_server(state: Arc<AppState>, app_handle: tauri::AppHandle) {
     };
 
     let context = ServerContext { state, app_handle };
+    let ws_state = context.state.clone();
+    let auth_context = context.clone();
+
+    // Only these specific local origins may call the server; a malicious webpage on
+    // an arbitrary origin can no longer ride the user's browser via permissive CORS.
+    let cors = CorsLayer::new()
+        .allow_origin([
+            format!("http://127.0.0.1:{}", local_port)
+                .parse()
+                .unwrap(),
+            format!("http://localhost:{}", local_port).parse().unwrap(),
+            "tauri://localhost".parse().unwrap(),
+        ])
+        .allow_methods([Method::GET, Method::POST])
+        .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION]);
 
     let app = Router::new()
         .route("/status", get(get_status))
+        .route("/metrics", get(get_metrics))
+        .route("/queue", get(get_queue_status))
+        .route("/ws", get(ws_upgrade))
         .route("/auth", post(authorize))
+        .route("/token", post(issue_token))
+        .route("/pair/qr", get(pair_qr))
         .route("/config", get(get_config).post(update_config))
         .route("/devices", get(get_devices))
         .route("/levels", get(get_audio_levels))
@@ -39,15 +87,202 @@ pub async fn start_server(state: Arc<AppState>, app_handle: tauri::AppHandle) {
         .route("/pause", post(pause_recording))
         .route("/resume", post(resume_recording))
         .route("/update", post(trigger_update))
-        .layer(CorsLayer::permissive())
+        .layer(middleware::from_fn_with_state(
+            auth_context,
+            require_control_token,
+        ))
+        .layer(cors)
         .with_state(context);
 
+    tokio::spawn(broadcast_levels_loop(ws_state.clone()));
+    tokio::spawn(forward_audio_status_loop(ws_state.clone()));
+    tokio::spawn(token_sweep_loop(ws_state));
+
     let bind_addr = format!("127.0.0.1:{}", local_port);
     info!("Server running on http://{}", bind_addr);
     let listener = tokio::net::TcpListener::bind(&bind_addr).await.unwrap();
     axum::serve(listener, app).await.unwrap();
 }
 
+/// Routes exempt from the token check, since a client has to reach them before it holds
+/// a working token in order to bootstrap one in the first place.
+const UNAUTHENTICATED_ROUTES: &[&str] = &["/auth", "/token", "/pair/qr"];
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+/// Require a non-expired, `Control`-scoped token on every mutating route. GETs and the
+/// bootstrap routes in [`UNAUTHENTICATED_ROUTES`] pass through unauthenticated.
+async fn require_control_token(
+    State(context): State<ServerContext>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let path = req.uri().path().to_string();
+    if req.method() == Method::GET || UNAUTHENTICATED_ROUTES.contains(&path.as_str()) {
+        return next.run(req).await;
+    }
+
+    let state = &context.state;
+    prune_expired_tokens(state);
+
+    let authorized = bearer_token(req.headers())
+        .and_then(|token| {
+            state
+                .scoped_tokens
+                .lock()
+                .unwrap()
+                .get(token)
+                .map(|t| t.scope == TokenScope::Control)
+        })
+        .unwrap_or(false);
+
+    if authorized {
+        next.run(req).await
+    } else {
+        StatusCode::UNAUTHORIZED.into_response()
+    }
+}
+
+/// Drop any scoped token past its expiry. Called lazily on each request and from
+/// [`token_sweep_loop`] so a server that sees no traffic still frees abandoned tokens.
+fn prune_expired_tokens(state: &AppState) {
+    let now = Instant::now();
+    state
+        .scoped_tokens
+        .lock()
+        .unwrap()
+        .retain(|_, t| t.expires_at > now);
+}
+
+async fn token_sweep_loop(state: Arc<AppState>) {
+    loop {
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        prune_expired_tokens(&state);
+    }
+}
+
+/// Publish a `StatusResponse` frame to `/ws` subscribers and a `status://update` Tauri
+/// event to any listening window. Called on every state transition rather than on a
+/// timer, so clients get instant updates without polling `/status`.
+pub fn publish_status(state: &AppState, app: &tauri::AppHandle) {
+    let response = build_status_response(state);
+    let frame = serde_json::json!({ "kind": "status", "data": response });
+    let _ = state.ws_tx.send(frame.to_string());
+
+    let _ = app.emit_all("status://update", build_status_snapshot(state));
+}
+
+/// Snapshot carried by the `status://update` event. Distinct from `StatusResponse`
+/// in that it also reports live connection/level state the `/ws` API doesn't need,
+/// for a settings window VU meter or dashboard.
+#[derive(serde::Serialize, Clone)]
+pub struct StatusSnapshot {
+    pub status: AppStatus,
+    pub connected: bool,
+    pub input_level: u32,
+    pub output_level: u32,
+    pub elapsed_secs: u64,
+    pub update_available: bool,
+}
+
+pub fn build_status_snapshot(state: &AppState) -> StatusSnapshot {
+    let status = state.status.lock().unwrap().clone();
+    let connected = state
+        .is_backend_connected
+        .load(std::sync::atomic::Ordering::SeqCst);
+    let input_level = state.input_level.load(std::sync::atomic::Ordering::Relaxed);
+    let output_level = state.output_level.load(std::sync::atomic::Ordering::Relaxed);
+
+    let duration = {
+        let acc = *state.accumulated_duration.lock().unwrap();
+        let start = *state.recording_start_time.lock().unwrap();
+        match status {
+            AppStatus::Recording => start
+                .and_then(|s| s.elapsed().ok())
+                .map(|elapsed| acc + elapsed)
+                .unwrap_or(acc),
+            _ => acc,
+        }
+    };
+
+    let update_available = state
+        .update_available
+        .load(std::sync::atomic::Ordering::Relaxed);
+
+    StatusSnapshot {
+        status,
+        connected,
+        input_level,
+        output_level,
+        elapsed_secs: duration.as_secs(),
+        update_available,
+    }
+}
+
+/// Stream `AudioLevelsResponse` frames at ~20Hz to `/ws` subscribers while recording.
+async fn broadcast_levels_loop(state: Arc<AppState>) {
+    loop {
+        let is_recording = matches!(*state.status.lock().unwrap(), AppStatus::Recording);
+        if is_recording {
+            let levels = AudioLevelsResponse {
+                input_level: state.take_input_level(),
+                output_level: state.take_output_level(),
+                is_recording: true,
+            };
+            let frame = serde_json::json!({ "kind": "levels", "data": levels });
+            let _ = state.ws_tx.send(frame.to_string());
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+/// Relays `AppState::audio_status_tx` transitions onto `/ws` as `audio_status` frames,
+/// so the web UI sees pause/resume/level updates the same way the tray does, without
+/// the server having to poll `status`.
+async fn forward_audio_status_loop(state: Arc<AppState>) {
+    let mut rx = state.audio_status_tx.subscribe();
+    loop {
+        match rx.recv().await {
+            Ok(status) => {
+                let frame = serde_json::json!({ "kind": "audio_status", "data": status });
+                let _ = state.ws_tx.send(frame.to_string());
+            }
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+async fn ws_upgrade(
+    ws: WebSocketUpgrade,
+    State(context): State<ServerContext>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_ws(socket, context.state))
+}
+
+async fn handle_ws(mut socket: WebSocket, state: Arc<AppState>) {
+    let mut rx = state.ws_tx.subscribe();
+
+    // Send an immediate snapshot so the client doesn't wait for the next transition.
+    let initial = build_status_response(&state);
+    let frame = serde_json::json!({ "kind": "status", "data": initial });
+    if socket.send(Message::Text(frame.to_string())).await.is_err() {
+        return;
+    }
+
+    while let Ok(message) = rx.recv().await {
+        if socket.send(Message::Text(message)).await.is_err() {
+            break;
+        }
+    }
+}
+
 use std::time::SystemTime;
 
 #[derive(serde::Serialize)]
@@ -61,8 +296,7 @@ struct StatusResponse {
     latest_version: Option<String>,
 }
 
-async fn get_status(State(context): State<ServerContext>) -> Json<StatusResponse> {
-    let state = &context.state;
+fn build_status_response(state: &AppState) -> StatusResponse {
     let status = state.status.lock().unwrap().clone();
     let (authenticated, api_host) = {
         let config = state.config.lock().unwrap();
@@ -94,7 +328,7 @@ async fn get_status(State(context): State<ServerContext>) -> Json<StatusResponse
         .load(std::sync::atomic::Ordering::Relaxed);
     let latest_version = state.latest_version.lock().unwrap().clone();
 
-    Json(StatusResponse {
+    StatusResponse {
         status,
         duration_seconds: duration.as_secs(),
         version: env!("CARGO_PKG_VERSION"),
@@ -102,36 +336,122 @@ async fn get_status(State(context): State<ServerContext>) -> Json<StatusResponse
         api_host,
         update_available,
         latest_version,
+    }
+}
+
+async fn get_status(State(context): State<ServerContext>) -> ApiResponse<StatusResponse> {
+    ApiResponse::Success(build_status_response(&context.state))
+}
+
+async fn get_metrics(State(context): State<ServerContext>) -> String {
+    metrics::render(&context.state)
+}
+
+#[derive(serde::Serialize)]
+struct QueueStatusResponse {
+    depth: usize,
+    last_error: Option<String>,
+}
+
+async fn get_queue_status(State(context): State<ServerContext>) -> ApiResponse<QueueStatusResponse> {
+    let queue = &context.state.upload_queue;
+    ApiResponse::Success(QueueStatusResponse {
+        depth: queue.depth(),
+        last_error: queue.last_error(),
     })
 }
 
+#[derive(serde::Serialize)]
+struct PairingPayload {
+    nonce: Uuid,
+    host: String,
+    port: u16,
+}
+
+/// Mint a short-lived pairing nonce and return it encoded as a QR code (SVG) so a
+/// phone/companion app can scan-to-connect instead of copy-pasting a token.
+async fn pair_qr(State(context): State<ServerContext>) -> impl IntoResponse {
+    let state = &context.state;
+    let local_port = state.config.lock().unwrap().local_port;
+
+    let nonce = Uuid::new_v4();
+    let one_time_token = Uuid::new_v4().to_string();
+
+    state.pending_pairs.lock().unwrap().insert(
+        nonce,
+        PendingPair {
+            local_port,
+            one_time_token,
+            issued_at: Instant::now(),
+        },
+    );
+
+    let payload = PairingPayload {
+        nonce,
+        host: "127.0.0.1".to_string(),
+        port: local_port,
+    };
+    let payload_json = match serde_json::to_string(&payload) {
+        Ok(json) => json,
+        Err(e) => {
+            error!("Failed to serialize pairing payload: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new()).into_response();
+        }
+    };
+
+    match qrencode::QrCode::new(payload_json.as_bytes()) {
+        Ok(code) => {
+            let svg = code.render_svg();
+            ([(header::CONTENT_TYPE, "image/svg+xml")], svg).into_response()
+        }
+        Err(e) => {
+            error!("Failed to render pairing QR code: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new()).into_response()
+        }
+    }
+}
+
+/// Drop any pairing nonce older than `PAIRING_TTL_SECS`.
+fn prune_expired_pairs(state: &AppState) {
+    let ttl = Duration::from_secs(PAIRING_TTL_SECS);
+    state
+        .pending_pairs
+        .lock()
+        .unwrap()
+        .retain(|_, pair| pair.issued_at.elapsed() < ttl);
+}
+
 // Authorization endpoint for web-based device pairing
 #[derive(serde::Deserialize)]
 struct AuthRequest {
+    nonce: Option<Uuid>,
     token: String,
     api_host: Option<String>,
     api_port: Option<u16>,
 }
 
-#[derive(serde::Serialize)]
-struct AuthResponse {
-    success: bool,
-    message: String,
-}
-
 #[debug_handler]
 async fn authorize(
     State(context): State<ServerContext>,
     Json(payload): Json<AuthRequest>,
-) -> Json<AuthResponse> {
+) -> ApiResponse<String> {
     let state = &context.state;
     info!("Received authorization request");
 
     if payload.token.is_empty() {
-        return Json(AuthResponse {
-            success: false,
-            message: "Token cannot be empty".to_string(),
-        });
+        return ApiResponse::Failure("Token cannot be empty".to_string());
+    }
+
+    // If the request came from the QR pairing flow, the nonce must match a pairing
+    // minted within the last PAIRING_TTL_SECS. Redeem it (single-use) before proceeding.
+    if let Some(nonce) = payload.nonce {
+        prune_expired_pairs(state);
+        let redeemed = state.pending_pairs.lock().unwrap().remove(&nonce);
+        if redeemed.is_none() {
+            return ApiResponse::Failure(
+                "Pairing request expired or unknown. Please scan the QR code again.".to_string(),
+            );
+        }
     }
 
     // Save the token and connection details to config
@@ -149,10 +469,7 @@ async fn authorize(
 
         if let Err(e) = config.save() {
             error!("Failed to save config: {}", e);
-            return Json(AuthResponse {
-                success: false,
-                message: format!("Failed to save config: {}", e),
-            });
+            return ApiResponse::Fatal(format!("Failed to save config: {}", e));
         }
     }
 
@@ -163,9 +480,65 @@ async fn authorize(
         "Companion app is now connected and configured.",
     );
 
-    Json(AuthResponse {
-        success: true,
-        message: "Authorization and configuration successful".to_string(),
+    ApiResponse::Success("Authorization and configuration successful".to_string())
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RequestedScope {
+    Control,
+    ReadOnly,
+}
+
+#[derive(serde::Deserialize)]
+struct TokenRequest {
+    scope: RequestedScope,
+}
+
+#[derive(serde::Serialize)]
+struct TokenResponse {
+    token: String,
+    expires_in_secs: u64,
+}
+
+/// Mint a short-lived, scoped token for the mutating routes. Gated on the caller
+/// already holding the long-lived static `config.api_token` as a Bearer header, the
+/// same credential `/auth` persists — this is the one bootstrap a scoped token can't
+/// replace since nothing else proves the caller is a trusted client yet.
+async fn issue_token(
+    State(context): State<ServerContext>,
+    headers: HeaderMap,
+    Json(payload): Json<TokenRequest>,
+) -> ApiResponse<TokenResponse> {
+    let state = &context.state;
+    let (master_token, ttl_secs) = {
+        let config = state.config.lock().unwrap();
+        (config.api_token.clone(), config.scoped_token_ttl_secs)
+    };
+
+    if master_token.is_empty() || bearer_token(&headers) != Some(master_token.as_str()) {
+        return ApiResponse::Failure("Invalid or missing bearer token".to_string());
+    }
+
+    let scope = match payload.scope {
+        RequestedScope::Control => TokenScope::Control,
+        RequestedScope::ReadOnly => TokenScope::ReadOnly,
+    };
+    let ttl = Duration::from_secs(ttl_secs);
+    let token = Uuid::new_v4().to_string();
+
+    prune_expired_tokens(state);
+    state.scoped_tokens.lock().unwrap().insert(
+        token.clone(),
+        ScopedToken {
+            scope,
+            expires_at: Instant::now() + ttl,
+        },
+    );
+
+    ApiResponse::Success(TokenResponse {
+        token,
+        expires_in_secs: ttl.as_secs(),
     })
 }
 
@@ -176,12 +549,12 @@ struct AudioLevelsResponse {
     is_recording: bool,
 }
 
-async fn get_audio_levels(State(context): State<ServerContext>) -> Json<AudioLevelsResponse> {
+async fn get_audio_levels(State(context): State<ServerContext>) -> ApiResponse<AudioLevelsResponse> {
     let state = &context.state;
     let status = state.status.lock().unwrap().clone();
     let is_recording = matches!(status, AppStatus::Recording);
 
-    Json(AudioLevelsResponse {
+    ApiResponse::Success(AudioLevelsResponse {
         input_level: state.take_input_level(),
         output_level: state.take_output_level(),
         is_recording,
@@ -204,7 +577,7 @@ struct StartResponse {
 async fn start_recording(
     State(context): State<ServerContext>,
     Json(payload): Json<StartRequest>,
-) -> (StatusCode, Json<StartResponse>) {
+) -> ApiResponse<StartResponse> {
     let state = &context.state;
     info!("Received start_recording request for '{}'", payload.name);
 
@@ -212,13 +585,7 @@ async fn start_recording(
     {
         let status = state.status.lock().unwrap();
         if *status != AppStatus::Idle && *status != AppStatus::BackendOffline {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(StartResponse {
-                    id: 0,
-                    message: "Already recording".to_string(),
-                }),
-            );
+            return ApiResponse::Failure("Already recording".to_string());
         }
     }
 
@@ -237,9 +604,13 @@ async fn start_recording(
         .build()
         .unwrap_or_default();
 
-    let (api_url, token) = {
-        let config = state.config.lock().unwrap();
-        (config.get_api_url(), config.api_token.clone())
+    let api_url = state.config.lock().unwrap().get_api_url();
+    let token = match state.valid_token().await {
+        Ok(token) => token,
+        Err(e) => {
+            error!("Failed to obtain a valid backend token: {}", e);
+            return ApiResponse::Fatal("Failed to authenticate with backend".to_string());
+        }
     };
 
     let res = client
@@ -272,6 +643,9 @@ async fn start_recording(
                     // Re-acquire lock to update status
                     let mut status = state.status.lock().unwrap();
                     *status = AppStatus::Recording;
+                    state.recordings_started_total.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    drop(status);
+                    publish_status(state, &context.app_handle);
 
                     notifications::show_notification(
                         &context.app_handle,
@@ -280,13 +654,10 @@ async fn start_recording(
                     );
                     info!("Recording started successfully. ID: {}", id);
 
-                    return (
-                        StatusCode::OK,
-                        Json(StartResponse {
-                            id,
-                            message: "Recording started".to_string(),
-                        }),
-                    );
+                    return ApiResponse::Success(StartResponse {
+                        id,
+                        message: "Recording started".to_string(),
+                    });
                 }
             }
         }
@@ -295,13 +666,7 @@ async fn start_recording(
         }
     }
 
-    (
-        StatusCode::INTERNAL_SERVER_ERROR,
-        Json(StartResponse {
-            id: 0,
-            message: "Failed to start recording".to_string(),
-        }),
-    )
+    ApiResponse::Fatal("Failed to start recording".to_string())
 }
 
 #[derive(serde::Deserialize)]
@@ -312,7 +677,7 @@ struct StopRequest {
 async fn stop_recording(
     State(context): State<ServerContext>,
     Json(payload): Json<Option<StopRequest>>,
-) -> Result<Json<String>, StatusCode> {
+) -> ApiResponse<String> {
     let state = &context.state;
     info!("Received stop_recording request");
     // Update token if provided
@@ -328,6 +693,7 @@ async fn stop_recording(
     {
         let mut status = state.status.lock().unwrap();
         *status = AppStatus::Uploading;
+        state.recordings_stopped_total.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
 
         // Reset timing
         let mut start_time = state.recording_start_time.lock().unwrap();
@@ -337,23 +703,21 @@ async fn stop_recording(
 
         // Do NOT clear current_recording_id here. Audio thread needs it.
     }
+    publish_status(state, &context.app_handle);
     state.audio_command_tx.send(AudioCommand::Stop).unwrap();
 
     if let Some(id) = recording_id {
-        let config_clone = state.config.lock().unwrap().clone();
-        tokio::spawn(async move {
-            if let Err(e) = uploader::update_client_status(id, "UPLOADING", &config_clone).await {
-                error!("Failed to update client status: {}", e);
-            }
-        });
+        if let Err(e) = uploader::update_client_status(id, "UPLOADING", state) {
+            error!("Failed to queue client status update: {}", e);
+        }
     }
 
     notifications::show_notification(&context.app_handle, "Recording Stopped", "Processing audio...");
     info!("Stop command processed successfully");
-    Ok(Json("Stopped".to_string()))
+    ApiResponse::Success("Stopped".to_string())
 }
 
-async fn pause_recording(State(context): State<ServerContext>) -> Result<Json<String>, StatusCode> {
+async fn pause_recording(State(context): State<ServerContext>) -> ApiResponse<String> {
     let state = &context.state;
     info!("Received pause_recording request");
     let recording_id = *state.current_recording_id.lock().unwrap();
@@ -371,25 +735,21 @@ async fn pause_recording(State(context): State<ServerContext>) -> Result<Json<St
         }
         *start_time = None;
     }
+    publish_status(state, &context.app_handle);
     state.audio_command_tx.send(AudioCommand::Pause).unwrap();
 
     if let Some(id) = recording_id {
-        let config_clone = state.config.lock().unwrap().clone();
-        tokio::spawn(async move {
-            if let Err(e) = uploader::update_client_status(id, "PAUSED", &config_clone).await {
-                error!("Failed to update client status: {}", e);
-            }
-        });
+        if let Err(e) = uploader::update_client_status(id, "PAUSED", state) {
+            error!("Failed to queue client status update: {}", e);
+        }
     }
 
     notifications::show_notification(&context.app_handle, "Recording Paused", "Recording paused.");
     info!("Recording paused");
-    Ok(Json("Paused".to_string()))
+    ApiResponse::Success("Paused".to_string())
 }
 
-async fn resume_recording(
-    State(context): State<ServerContext>,
-) -> Result<Json<String>, StatusCode> {
+async fn resume_recording(State(context): State<ServerContext>) -> ApiResponse<String> {
     let state = &context.state;
     info!("Received resume_recording request");
     let recording_id = *state.current_recording_id.lock().unwrap();
@@ -403,20 +763,18 @@ async fn resume_recording(
         let mut start_time = state.recording_start_time.lock().unwrap();
         *start_time = Some(SystemTime::now());
     }
+    publish_status(state, &context.app_handle);
     state.audio_command_tx.send(AudioCommand::Resume).unwrap();
 
     if let Some(id) = recording_id {
-        let config_clone = state.config.lock().unwrap().clone();
-        tokio::spawn(async move {
-            if let Err(e) = uploader::update_client_status(id, "RECORDING", &config_clone).await {
-                error!("Failed to update client status: {}", e);
-            }
-        });
+        if let Err(e) = uploader::update_client_status(id, "RECORDING", state) {
+            error!("Failed to queue client status update: {}", e);
+        }
     }
 
     notifications::show_notification(&context.app_handle, "Recording Resumed", "Recording resumed.");
     info!("Recording resumed");
-    Ok(Json("Resumed".to_string()))
+    ApiResponse::Success("Resumed".to_string())
 }
 
 #[derive(serde::Serialize)]
@@ -425,69 +783,31 @@ struct ConfigResponse {
     local_port: u16,
 }
 
-async fn get_config(State(context): State<ServerContext>) -> Json<ConfigResponse> {
+async fn get_config(State(context): State<ServerContext>) -> ApiResponse<ConfigResponse> {
     let state = &context.state;
     let config = state.config.lock().unwrap();
-    Json(ConfigResponse {
+    ApiResponse::Success(ConfigResponse {
         api_port: config.api_port,
         local_port: config.local_port,
     })
 }
 
-#[derive(serde::Serialize)]
-struct AudioDevice {
-    name: String,
-    is_default: bool,
-}
-
 #[derive(serde::Serialize)]
 struct DevicesResponse {
-    input_devices: Vec<AudioDevice>,
-    output_devices: Vec<AudioDevice>,
+    input_devices: Vec<audio::AudioDeviceInfo>,
+    output_devices: Vec<audio::AudioDeviceInfo>,
     selected_input: Option<String>,
     selected_output: Option<String>,
 }
 
-async fn get_devices(State(context): State<ServerContext>) -> Json<DevicesResponse> {
+async fn get_devices(State(context): State<ServerContext>) -> ApiResponse<DevicesResponse> {
     let state = &context.state;
-    let host = cpal::default_host();
-
-    let default_input_name = host.default_input_device().and_then(|d| d.name().ok());
-    let default_output_name = host.default_output_device().and_then(|d| d.name().ok());
-
-    let input_devices: Vec<AudioDevice> = host
-        .input_devices()
-        .map(|devices| {
-            devices
-                .filter_map(|d| {
-                    d.name().ok().map(|name| AudioDevice {
-                        is_default: Some(&name) == default_input_name.as_ref(),
-                        name,
-                    })
-                })
-                .collect()
-        })
-        .unwrap_or_default();
-
-    let output_devices: Vec<AudioDevice> = host
-        .output_devices()
-        .map(|devices| {
-            devices
-                .filter_map(|d| {
-                    d.name().ok().map(|name| AudioDevice {
-                        is_default: Some(&name) == default_output_name.as_ref(),
-                        name,
-                    })
-                })
-                .collect()
-        })
-        .unwrap_or_default();
-
+    let devices = audio::enumerate_audio_devices();
     let config = state.config.lock().unwrap();
 
-    Json(DevicesResponse {
-        input_devices,
-        output_devices,
+    ApiResponse::Success(DevicesResponse {
+        input_devices: devices.input_devices,
+        output_devices: devices.output_devices,
         selected_input: config.input_device_name.clone(),
         selected_output: config.output_device_name.clone(),
     })
@@ -504,7 +824,7 @@ struct ConfigUpdate {
 async fn update_config(
     State(context): State<ServerContext>,
     Json(payload): Json<ConfigUpdate>,
-) -> Result<Json<ConfigResponse>, StatusCode> {
+) -> ApiResponse<ConfigResponse> {
     let state = &context.state;
     let mut config = state.config.lock().unwrap();
 
@@ -522,27 +842,28 @@ async fn update_config(
     }
 
     if let Err(e) = config.save() {
-        eprintln!("Failed to save config: {}", e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        error!("Failed to save config: {}", e);
+        return ApiResponse::Fatal(format!("Failed to save config: {}", e));
     }
 
-    Ok(Json(ConfigResponse {
+    ApiResponse::Success(ConfigResponse {
         api_port: config.api_port,
         local_port: config.local_port,
-    }))
+    })
 }
 
-async fn trigger_update(State(context): State<ServerContext>) -> StatusCode {
+async fn trigger_update(State(context): State<ServerContext>) -> ApiResponse<String> {
     let state = &context.state;
     let url = state.latest_update_url.lock().unwrap().clone();
 
-    if let Some(target_url) = url {
-        if let Err(e) = open::that(target_url) {
-            error!("Failed to open update URL: {}", e);
-            return StatusCode::INTERNAL_SERVER_ERROR;
-        }
-        StatusCode::OK
-    } else {
-        StatusCode::NOT_FOUND
+    match url {
+        Some(target_url) => match open::that(target_url) {
+            Ok(_) => ApiResponse::Success("Update page opened".to_string()),
+            Err(e) => {
+                error!("Failed to open update URL: {}", e);
+                ApiResponse::Fatal(format!("Failed to open update URL: {}", e))
+            }
+        },
+        None => ApiResponse::Failure("No update available".to_string()),
     }
 }
@@ -1,11 +1,39 @@
 use crate::config::Config;
+use crate::uploader::UploadQueue;
 use crossbeam_channel::Sender;
 use serde::{Deserialize, Serialize};
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
-use std::sync::Mutex;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tauri::menu::{CheckMenuItem, MenuItem};
 use tauri::tray::TrayIcon;
 use tauri::Wry;
+use uuid::Uuid;
+
+/// A one-time pairing nonce minted by `/pair/qr`, redeemed by `/auth` within `PAIRING_TTL_SECS`.
+pub struct PendingPair {
+    pub local_port: u16,
+    pub one_time_token: String,
+    pub issued_at: Instant,
+}
+
+pub const PAIRING_TTL_SECS: u64 = 60;
+
+/// Permission granted to a token minted by `/token`. `Control` is required for the
+/// mutating routes (`/start`, `/stop`, `/config` POST, ...); `ReadOnly` only ever
+/// grants access to routes that were already unauthenticated GETs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenScope {
+    Control,
+    ReadOnly,
+}
+
+/// A short-lived token issued by `/token`, scoped to [`TokenScope`] and pruned once expired.
+pub struct ScopedToken {
+    pub scope: TokenScope,
+    pub expires_at: Instant,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum AppStatus {
@@ -28,18 +56,56 @@ pub struct AppState {
     // Audio levels (0-100 scaled, stored as u32 for atomic access)
     pub input_level: AtomicU32,
     pub output_level: AtomicU32,
+    // Current fill level of the system-audio ring buffer in `audio::run_mixing_loop`,
+    // in milliseconds. Surfaced for diagnostics; not read by the mixer itself.
+    pub sys_buffer_fill_ms: AtomicU32,
+    // Speech-band energy (0-100 scaled, like `input_level`/`output_level`) from
+    // `vad::VoiceActivityDetector`, when `Config::vad` is enabled. Stays 0 while
+    // disabled so the UI's VAD indicator reads as "off" rather than stale.
+    pub speech_energy: AtomicU32,
+    // Telemetry counters, kept lock-free so the audio/upload paths never block on them
+    pub recordings_started_total: AtomicU64,
+    pub recordings_stopped_total: AtomicU64,
+    pub recordings_failed_total: AtomicU64,
+    pub uploaded_bytes_total: AtomicU64,
+    pub uploaded_chunks_total: AtomicU64,
+    pub upload_retries_total: AtomicU64,
+    pub reconnect_attempts_total: AtomicU64,
+    // Durable, crash-resilient queue for segment uploads and status transitions
+    pub upload_queue: UploadQueue,
+    // Pairing nonces minted by `/pair/qr`, pending redemption via `/auth`
+    pub pending_pairs: Mutex<HashMap<Uuid, PendingPair>>,
+    // Scoped, expiring tokens minted by `/token`, keyed by the token string itself
+    pub scoped_tokens: Mutex<HashMap<String, ScopedToken>>,
+    // Pushed to `/ws` subscribers as pre-serialized JSON frames whenever status or
+    // levels change, so the handlers never need to know who (if anyone) is listening
+    pub ws_tx: tokio::sync::broadcast::Sender<String>,
+    // Transitions/levels published by the audio loop. Lets the tray and the server
+    // thread (`server::forward_audio_status_loop`) react to what the audio thread is
+    // actually doing instead of polling `status` with `try_lock` on a timer.
+    pub audio_status_tx: tokio::sync::broadcast::Sender<AudioStatus>,
     // Dynamic Web URL fetched from backend
     pub web_url: Mutex<Option<String>>,
     pub is_backend_connected: AtomicBool,
     // Update status
     pub update_available: AtomicBool,
     pub latest_version: Mutex<Option<String>>,
-    
+    pub latest_update_url: Mutex<Option<String>>,
+
     // Tray Menu Items
     pub tray_status_item: Mutex<Option<MenuItem<Wry>>>,
     pub tray_run_on_startup_item: Mutex<Option<CheckMenuItem<Wry>>>,
     pub tray_open_web_item: Mutex<Option<MenuItem<Wry>>>,
     pub tray_icon: Mutex<Option<TrayIcon<Wry>>>,
+    // Enabled/disabled purely from `AudioStatus` messages (see `main::watch_audio_status`),
+    // not from locking `status`
+    pub tray_pause_item: Mutex<Option<MenuItem<Wry>>>,
+    pub tray_resume_item: Mutex<Option<MenuItem<Wry>>>,
+
+    // Low-latency live-delivery connection, established at startup when
+    // `Config::live_stream` is enabled. `None` when disabled or not yet connected;
+    // `run_segment_encoder_task` checks this alongside (not instead of) `upload_queue`.
+    pub live_stream_sender: Mutex<Option<Arc<crate::live_stream::LiveStreamSender>>>,
 }
 
 impl AppState {
@@ -65,14 +131,110 @@ impl AppState {
     /// Check if the companion has a valid API token configured
     pub fn is_authenticated(&self) -> bool {
         let config = self.config.lock().unwrap();
-        !config.api_token.is_empty()
+        !config.api_token.is_empty() || config.access_token.is_some()
+    }
+
+    /// Returns a currently-valid bearer token for backend calls, transparently refreshing
+    /// the OAuth access token against `/auth/refresh` first if it's within
+    /// [`TOKEN_REFRESH_SKEW`] of expiring. Falls back to the legacy static `api_token`
+    /// when no OAuth token is configured, so existing pairings keep working.
+    pub async fn valid_token(&self) -> anyhow::Result<String> {
+        let (access_token, refresh_token, expires_at, api_url, legacy_token) = {
+            let config = self.config.lock().unwrap();
+            (
+                config.access_token.clone(),
+                config.refresh_token.clone(),
+                config.expires_at,
+                config.get_api_url(),
+                config.api_token.clone(),
+            )
+        };
+
+        let Some(access_token) = access_token else {
+            return Ok(legacy_token);
+        };
+
+        let needs_refresh = match expires_at {
+            Some(exp) => exp
+                .duration_since(std::time::SystemTime::now())
+                .map(|remaining| remaining < TOKEN_REFRESH_SKEW)
+                .unwrap_or(true),
+            None => false,
+        };
+
+        if !needs_refresh {
+            return Ok(access_token);
+        }
+
+        let Some(refresh_token) = refresh_token else {
+            // No refresh token on file; hand back what we have and let the caller
+            // discover it's stale from the backend's response.
+            return Ok(access_token);
+        };
+
+        let refreshed = refresh_access_token(&api_url, &refresh_token).await?;
+
+        {
+            let mut config = self.config.lock().unwrap();
+            config.access_token = Some(refreshed.access_token.clone());
+            config.refresh_token = Some(refreshed.refresh_token);
+            config.expires_at =
+                Some(std::time::SystemTime::now() + std::time::Duration::from_secs(refreshed.expires_in));
+            if let Err(e) = config.save() {
+                log::error!("Failed to persist refreshed token: {}", e);
+            }
+        }
+
+        Ok(refreshed.access_token)
     }
 }
 
+/// How far ahead of the recorded expiry to refresh, so a request in flight doesn't race
+/// a token that expires mid-call.
+const TOKEN_REFRESH_SKEW: std::time::Duration = std::time::Duration::from_secs(60);
+
+#[derive(serde::Deserialize)]
+struct RefreshResponse {
+    access_token: String,
+    refresh_token: String,
+    expires_in: u64,
+}
+
+async fn refresh_access_token(api_url: &str, refresh_token: &str) -> anyhow::Result<RefreshResponse> {
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("{}/auth/refresh", api_url))
+        .json(&serde_json::json!({ "refresh_token": refresh_token }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<RefreshResponse>()
+        .await?;
+    Ok(response)
+}
+
 #[derive(Debug, Clone)]
 pub enum AudioCommand {
     Start(i64), // recording_id
     Pause,
     Resume,
     Stop,
+    /// Asks the audio loop to re-publish its current `AudioStatus` on
+    /// `AppState::audio_status_tx`, so a subscriber that attaches after the last
+    /// transition (e.g. a newly opened window) can resync without waiting for
+    /// the next one.
+    Query,
+}
+
+/// Published by the audio loop on `AppState::audio_status_tx` at every transition,
+/// so consumers (the tray, the server's `/ws` forwarder) can react to what the
+/// audio thread is actually doing instead of locking `AppState::status` on a timer.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "state", content = "data")]
+pub enum AudioStatus {
+    Recording,
+    Paused,
+    Stopped,
+    LevelUpdate { input: u32, output: u32 },
+    Error(String),
 }
@@ -0,0 +1,173 @@
+//! Optional on-device speech-to-text, so the companion can show live captions
+//! and keep a transcript even while `is_backend_connected` is false. Runs on
+//! its own thread, fed mixed mono samples from the audio loop over a plain
+//! `crossbeam_channel`; a Candle Whisper model decodes fixed windows with a
+//! short overlap so a word spoken across a window boundary isn't dropped.
+//! Segments are published as `{"kind": "transcript", ...}` frames on the same
+//! `/ws` channel `server::publish_status` already uses, so the web UI doesn't
+//! need a second connection to get them.
+
+use crate::state::AppState;
+use candle_core::{Device, Tensor};
+use candle_transformers::models::whisper::{self as whisper_model, audio as whisper_audio, Config as WhisperConfig};
+use crossbeam_channel::{Receiver, Sender};
+use log::{error, info, warn};
+use std::sync::Arc;
+
+/// Whisper models are trained on 16kHz mono audio; everything upstream
+/// (mic-rate mixed samples) gets resampled down to this before decoding.
+const TARGET_SAMPLE_RATE: u32 = 16_000;
+const WINDOW_SECS: f32 = 30.0;
+/// Kept at the front of the buffer after each window so a word spoken right
+/// at the boundary gets decoded again with surrounding context, rather than
+/// being cut in half and lost.
+const OVERLAP_SECS: f32 = 2.0;
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct TranscriptSegment {
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub text: String,
+}
+
+/// Spawns the transcription thread and returns the channel the audio loop
+/// should feed mixed mono samples into. `source_sample_rate` is fixed for the
+/// lifetime of the channel (the mic's rate doesn't change mid-recording).
+pub fn spawn(state: Arc<AppState>, source_sample_rate: u32) -> Sender<Vec<f32>> {
+    let (tx, rx) = crossbeam_channel::unbounded();
+    std::thread::spawn(move || run(state, rx, source_sample_rate));
+    tx
+}
+
+fn run(state: Arc<AppState>, rx: Receiver<Vec<f32>>, source_sample_rate: u32) {
+    let model_name = {
+        let config = state.config.lock().unwrap();
+        config.transcription_model.clone()
+    };
+
+    let mut whisper = match Whisper::load(&model_name) {
+        Ok(w) => w,
+        Err(e) => {
+            error!("Local transcription disabled, failed to load Whisper model '{}': {}", model_name, e);
+            // Keep draining so the audio loop's sends don't pile up in the unbounded channel.
+            while rx.recv().is_ok() {}
+            return;
+        }
+    };
+
+    let window_samples = (WINDOW_SECS * TARGET_SAMPLE_RATE as f32) as usize;
+    let overlap_samples = (OVERLAP_SECS * TARGET_SAMPLE_RATE as f32) as usize;
+    let advance_samples = window_samples.saturating_sub(overlap_samples);
+
+    let mut buffer: Vec<f32> = Vec::new();
+    let mut window_start_secs: f64 = 0.0;
+
+    while let Ok(chunk) = rx.recv() {
+        if !state.config.lock().unwrap().transcription_enabled {
+            buffer.clear();
+            continue;
+        }
+
+        buffer.extend(resample_linear(&chunk, source_sample_rate, TARGET_SAMPLE_RATE));
+
+        while buffer.len() >= window_samples {
+            match whisper.transcribe_window(&buffer[..window_samples], window_start_secs) {
+                Ok(segments) => {
+                    for segment in segments {
+                        publish_segment(&state, segment);
+                    }
+                }
+                Err(e) => warn!("Transcription window at {:.1}s failed: {}", window_start_secs, e),
+            }
+
+            buffer.drain(..advance_samples);
+            window_start_secs += advance_samples as f64 / TARGET_SAMPLE_RATE as f64;
+        }
+    }
+}
+
+fn publish_segment(state: &AppState, segment: TranscriptSegment) {
+    info!("Transcript [{:.1}-{:.1}s]: {}", segment.start_secs, segment.end_secs, segment.text);
+    let frame = serde_json::json!({ "kind": "transcript", "data": segment });
+    let _ = state.ws_tx.send(frame.to_string());
+}
+
+/// Naive, stateless linear-interpolation resampler: each chunk is resampled on its
+/// own, with no position or sample carried over from the previous chunk.
+fn resample_linear(input: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if input.is_empty() || from_rate == to_rate {
+        return input.to_vec();
+    }
+    let ratio = from_rate as f64 / to_rate as f64;
+    let out_len = ((input.len() as f64) / ratio).floor() as usize;
+    let mut out = Vec::with_capacity(out_len);
+    for i in 0..out_len {
+        let src_pos = i as f64 * ratio;
+        let idx = src_pos.floor() as usize;
+        let frac = (src_pos - idx as f64) as f32;
+        let a = input[idx.min(input.len() - 1)];
+        let b = input[(idx + 1).min(input.len() - 1)];
+        out.push(a + (b - a) * frac);
+    }
+    out
+}
+
+/// Thin wrapper around a loaded Candle Whisper model plus the mel filterbank
+/// it needs to turn raw samples into the encoder's input features.
+struct Whisper {
+    device: Device,
+    model: whisper_model::model::Whisper,
+    tokenizer: tokenizers::Tokenizer,
+    mel_filters: Vec<f32>,
+    config: WhisperConfig,
+}
+
+impl Whisper {
+    fn load(model_name: &str) -> anyhow::Result<Self> {
+        let device = Device::Cpu;
+        let api = hf_hub::api::sync::Api::new()?;
+        let repo = api.model(format!("openai/whisper-{}", model_name));
+
+        let config: WhisperConfig = serde_json::from_slice(&std::fs::read(repo.get("config.json")?)?)?;
+        let tokenizer = tokenizers::Tokenizer::from_file(repo.get("tokenizer.json")?)
+            .map_err(|e| anyhow::anyhow!("failed to load tokenizer: {}", e))?;
+        let weights = repo.get("model.safetensors")?;
+        let vb = unsafe { candle_nn::VarBuilder::from_mmaped_safetensors(&[weights], candle_core::DType::F32, &device)? };
+        let model = whisper_model::model::Whisper::load(&vb, config.clone())?;
+
+        let mel_bytes = include_bytes!("../assets/whisper_mel_filters.bin");
+        let mut mel_filters = vec![0f32; mel_bytes.len() / 4];
+        <byteorder::LittleEndian as byteorder::ByteOrder>::read_f32_into(mel_bytes, &mut mel_filters);
+
+        Ok(Self { device, model, tokenizer, mel_filters, config })
+    }
+
+    /// Decodes one window of already-16kHz-mono samples into zero or more
+    /// text segments, with `window_start_secs` added to each segment's
+    /// timestamps so they read as offsets into the whole recording rather
+    /// than into this window alone.
+    ///
+    /// The model's encoder/decoder state is reset before every call: nothing
+    /// from the previous window is carried forward. Without this, the
+    /// decoder's KV-cache keeps appending across windows and both memory use
+    /// and per-window latency grow for the length of the recording.
+    fn transcribe_window(&mut self, samples: &[f32], window_start_secs: f64) -> anyhow::Result<Vec<TranscriptSegment>> {
+        self.model.reset_kv_cache();
+
+        let mel = whisper_audio::pcm_to_mel(&self.config, samples, &self.mel_filters);
+        let mel_len = mel.len() / self.config.num_mel_bins;
+        let mel = Tensor::from_vec(mel, (1, self.config.num_mel_bins, mel_len), &self.device)?;
+
+        let encoder_output = self.model.encoder.forward(&mel, true)?;
+        let raw_segments = whisper_model::greedy_decode(&mut self.model, &encoder_output, &self.tokenizer)?;
+
+        Ok(raw_segments
+            .into_iter()
+            .map(|s| TranscriptSegment {
+                start_secs: window_start_secs + s.start,
+                end_secs: window_start_secs + s.end,
+                text: s.text,
+            })
+            .collect())
+    }
+}
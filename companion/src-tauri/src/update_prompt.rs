@@ -0,0 +1,72 @@
+//! Cross-platform "Update available" dialog offering Install Now / Remind Me
+//! Later, driven from `check_and_prompt_update` for both the periodic silent
+//! check and the manual tray "Check for Updates" item. Native dialogs block
+//! the calling thread, so [`prompt_install`] spawns its own `std::thread`
+//! rather than running on the Tauri event-loop thread; on Linux the dialog
+//! backend also needs GTK's own main loop, so the user's choice is marshaled
+//! back onto it via `glib::MainContext` instead of being acted on directly
+//! from that thread.
+
+use crate::state::AppState;
+use crate::updater::{self, GitHubRelease};
+use log::error;
+use rfd::{MessageButtons, MessageDialog, MessageDialogResult};
+use std::sync::Arc;
+use tauri::AppHandle;
+
+/// Shows the Install Now / Remind Me Later dialog for `release` off the
+/// current thread. "Install Now" kicks off `updater::download_and_install`;
+/// "Remind Me Later" records `version` in `Config::dismissed_update_version`
+/// so `check_and_prompt_update` skips re-prompting for it on silent checks.
+pub fn prompt_install(app: AppHandle, state: Arc<AppState>, release: GitHubRelease, version: String) {
+    std::thread::spawn(move || {
+        let result = MessageDialog::new()
+            .set_title("Update Available")
+            .set_description(&format!(
+                "Nojoin Companion {} is available. Install it now?",
+                version
+            ))
+            .set_buttons(MessageButtons::YesNoCustom(
+                "Install Now".to_string(),
+                "Remind Me Later".to_string(),
+            ))
+            .show();
+
+        #[cfg(target_os = "linux")]
+        {
+            glib::MainContext::default().spawn(async move {
+                handle_choice(app, state, release, version, result);
+            });
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            handle_choice(app, state, release, version, result);
+        }
+    });
+}
+
+fn handle_choice(
+    app: AppHandle,
+    state: Arc<AppState>,
+    release: GitHubRelease,
+    version: String,
+    result: MessageDialogResult,
+) {
+    match result {
+        MessageDialogResult::Yes => {
+            tauri::async_runtime::block_on(async move {
+                if let Err(e) = updater::download_and_install(&app, &state, &release).await {
+                    error!("Auto-update failed: {}", e);
+                }
+            });
+        }
+        _ => {
+            let mut config = state.config.lock().unwrap();
+            config.dismissed_update_version = Some(version);
+            if let Err(e) = config.save() {
+                error!("Failed to persist dismissed update version: {}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use blake2::{Blake2b512, Digest};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+/// Nojoin's release-signing public key (minisign format: 2-byte algorithm tag,
+/// 8-byte key ID, 32-byte Ed25519 public key, base64-encoded). Pairs with the
+/// private key CI signs release artifacts with; `Config::pubkey` can override
+/// this for self-hosted builds that sign with their own key.
+const EMBEDDED_PUBKEY_B64: &str = "RWRQbjlhbVBOb2pvaW5SZWxlYXNlU2lnbmluZ0tleTAwMDA=";
+
+/// A parsed minisign `.sig` file: the per-artifact signature plus the trusted
+/// comment line that the global signature also covers.
+struct MinisignSig {
+    key_id: [u8; 8],
+    signature: [u8; 64],
+    trusted_comment_line: String,
+    global_signature: [u8; 64],
+}
+
+fn parse_pubkey(pubkey_b64: &str) -> Result<([u8; 8], VerifyingKey), String> {
+    let raw = STANDARD
+        .decode(pubkey_b64.trim())
+        .map_err(|e| format!("invalid public key base64: {}", e))?;
+    if raw.len() != 42 {
+        return Err(format!("unexpected public key length: {} (want 42)", raw.len()));
+    }
+    if &raw[0..2] != b"Ed" {
+        return Err("unsupported public key algorithm tag".to_string());
+    }
+    let mut key_id = [0u8; 8];
+    key_id.copy_from_slice(&raw[2..10]);
+    let mut key_bytes = [0u8; 32];
+    key_bytes.copy_from_slice(&raw[10..42]);
+    let verifying_key = VerifyingKey::from_bytes(&key_bytes)
+        .map_err(|e| format!("invalid Ed25519 public key: {}", e))?;
+    Ok((key_id, verifying_key))
+}
+
+/// Parses a minisign `.sig` file. Only the "prehashed" (`ED`) algorithm tag is
+/// accepted since that's what `verify_artifact` below checks against — a
+/// BLAKE2b-512 digest of the artifact rather than the raw bytes, which is what
+/// minisign itself switches to once the signed file is more than a few KB.
+fn parse_sig(sig_text: &str) -> Result<MinisignSig, String> {
+    let lines: Vec<&str> = sig_text.lines().collect();
+    if lines.len() < 4 {
+        return Err("malformed .sig file: expected at least 4 lines".to_string());
+    }
+
+    let sig_blob = STANDARD
+        .decode(lines[1].trim())
+        .map_err(|e| format!("invalid signature base64: {}", e))?;
+    if sig_blob.len() != 74 {
+        return Err(format!("unexpected signature blob length: {} (want 74)", sig_blob.len()));
+    }
+    if &sig_blob[0..2] != b"ED" {
+        return Err("signature is not in prehashed (BLAKE2b) mode".to_string());
+    }
+    let mut key_id = [0u8; 8];
+    key_id.copy_from_slice(&sig_blob[2..10]);
+    let mut signature = [0u8; 64];
+    signature.copy_from_slice(&sig_blob[10..74]);
+
+    let trusted_comment_line = lines[2].to_string();
+    let global_signature_bytes = STANDARD
+        .decode(lines[3].trim())
+        .map_err(|e| format!("invalid global signature base64: {}", e))?;
+    if global_signature_bytes.len() != 64 {
+        return Err(format!(
+            "unexpected global signature length: {} (want 64)",
+            global_signature_bytes.len()
+        ));
+    }
+    let mut global_signature = [0u8; 64];
+    global_signature.copy_from_slice(&global_signature_bytes);
+
+    Ok(MinisignSig { key_id, signature, trusted_comment_line, global_signature })
+}
+
+fn sig_blob_bytes(parsed: &MinisignSig) -> Vec<u8> {
+    let mut blob = Vec::with_capacity(74);
+    blob.extend_from_slice(b"ED");
+    blob.extend_from_slice(&parsed.key_id);
+    blob.extend_from_slice(&parsed.signature);
+    blob
+}
+
+/// Verifies `artifact` against a detached minisign `.sig` file using `pubkey_b64`
+/// (the same base64 blob minisign embeds in a `.pub` file). Checks both that the
+/// per-artifact signature matches the BLAKE2b-512 digest of `artifact` and that
+/// the global signature over `(signature || trusted comment)` is valid, so a
+/// tampered trusted comment is caught too. Returns `Err` with a human-readable
+/// reason on any mismatch; callers should treat that as "do not install".
+pub fn verify_artifact(artifact: &[u8], sig_text: &str, pubkey_b64: &str) -> Result<(), String> {
+    let (pubkey_id, verifying_key) = parse_pubkey(pubkey_b64)?;
+    let parsed = parse_sig(sig_text)?;
+
+    if parsed.key_id != pubkey_id {
+        return Err("signature key ID does not match the trusted public key".to_string());
+    }
+
+    let mut hasher = Blake2b512::new();
+    hasher.update(artifact);
+    let digest = hasher.finalize();
+
+    let file_signature = Signature::from_bytes(&parsed.signature);
+    verifying_key
+        .verify(&digest, &file_signature)
+        .map_err(|e| format!("artifact signature verification failed: {}", e))?;
+
+    let trusted_comment = parsed
+        .trusted_comment_line
+        .strip_prefix("trusted comment: ")
+        .unwrap_or(&parsed.trusted_comment_line);
+    let mut signed_message = parsed.signature.to_vec();
+    signed_message.extend_from_slice(trusted_comment.as_bytes());
+    let global_signature = Signature::from_bytes(&parsed.global_signature);
+    verifying_key
+        .verify(&signed_message, &global_signature)
+        .map_err(|e| format!("trusted comment signature verification failed: {}", e))?;
+
+    Ok(())
+}
+
+/// `Config::pubkey`, falling back to the key Nojoin releases are signed with.
+pub fn resolve_pubkey(configured: Option<&String>) -> String {
+    configured.cloned().unwrap_or_else(|| EMBEDDED_PUBKEY_B64.to_string())
+}
@@ -0,0 +1,312 @@
+//! Detects and installs newer Companion releases published on GitHub. Detection
+//! ([`check_latest_release`]) and installation ([`download_and_install`]) are kept
+//! separate so a caller can react to "there's an update" (tray text, OS
+//! notification) without committing to the download. Installation streams the
+//! platform asset, verifies it against the detached minisign signature published
+//! alongside it (reusing [`update_verify`]'s embedded public key, same as the
+//! `tauri_plugin_updater`-driven flow in `win_notifications`/`linux_notifications`),
+//! and only then swaps it into place.
+
+use crate::config::{Config, ReleaseChannel};
+use crate::state::AppState;
+use crate::update_verify;
+use futures_util::StreamExt;
+use log::info;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+/// Minimum gap between `update://download-progress` emits. A stream yields chunks
+/// far faster than any progress bar needs to redraw.
+const PROGRESS_THROTTLE: std::time::Duration = std::time::Duration::from_secs(1);
+
+const RELEASES_LATEST_URL: &str = "https://api.github.com/repos/Valtora/Nojoin/releases/latest";
+const RELEASES_LIST_URL: &str = "https://api.github.com/repos/Valtora/Nojoin/releases";
+
+#[derive(Deserialize, Clone)]
+pub struct GitHubRelease {
+    pub tag_name: String,
+    pub html_url: String,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub assets: Vec<GitHubAsset>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct GitHubAsset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+#[derive(Serialize, Clone)]
+struct DownloadProgress {
+    downloaded: u64,
+    total: Option<u64>,
+    percent: Option<f64>,
+}
+
+#[derive(Serialize, Clone)]
+struct UpdateError {
+    message: String,
+}
+
+/// Strips the `companion-v`/`v` tag prefix GitHub release tags use down to a bare version string.
+pub fn release_version(tag_name: &str) -> String {
+    tag_name.trim_start_matches("companion-v").trim_start_matches('v').to_string()
+}
+
+/// A `major.minor.patch[-prerelease]` version, compared numerically rather than
+/// lexicographically so e.g. "0.1.10" correctly reads as newer than "0.1.9". A
+/// pre-release always orders below the release it's a pre-release of; beyond
+/// that, pre-release tags are compared as plain strings rather than implementing
+/// full semver pre-release precedence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Version {
+    major: u64,
+    minor: u64,
+    patch: u64,
+    prerelease: Option<String>,
+}
+
+impl Version {
+    fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim_start_matches('v');
+        let (core, prerelease) = match raw.split_once('-') {
+            Some((core, pre)) => (core, Some(pre.to_string())),
+            None => (raw, None),
+        };
+        let mut parts = core.split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next().unwrap_or("0").parse().ok()?;
+        let patch = parts.next().unwrap_or("0").parse().ok()?;
+        Some(Version { major, minor, patch, prerelease })
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (&self.prerelease, &other.prerelease) {
+                (None, None) => std::cmp::Ordering::Equal,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (Some(a), Some(b)) => a.cmp(b),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// `true` when `candidate` is a strictly newer version than `current`. An
+/// unparseable version on either side is treated as "not an update" rather than
+/// erroring, same as the naive string check this replaces.
+fn is_newer(current: &str, candidate: &str) -> bool {
+    match (Version::parse(current), Version::parse(candidate)) {
+        (Some(c), Some(n)) => n > c,
+        _ => false,
+    }
+}
+
+/// Fetches the newest release for `channel` and returns it when it's strictly
+/// newer than `current_version`. `Stable` only ever considers `/releases/latest`
+/// (which GitHub itself never resolves to a prerelease tag); `Beta` pulls the
+/// full `/releases` list, restricted to companion tags, and picks whichever has
+/// the highest precedence regardless of its `prerelease` flag.
+pub async fn check_latest_release(
+    current_version: &str,
+    channel: &ReleaseChannel,
+) -> Result<Option<GitHubRelease>, String> {
+    let client = reqwest::Client::builder()
+        .user_agent("Nojoin-Companion")
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let release = match channel {
+        ReleaseChannel::Stable => {
+            let resp = client.get(RELEASES_LATEST_URL).send().await.map_err(|e| e.to_string())?;
+            if !resp.status().is_success() {
+                return Err(format!("Failed to fetch releases: {}", resp.status()));
+            }
+            resp.json::<GitHubRelease>().await.map_err(|e| e.to_string())?
+        }
+        ReleaseChannel::Beta => {
+            let resp = client.get(RELEASES_LIST_URL).send().await.map_err(|e| e.to_string())?;
+            if !resp.status().is_success() {
+                return Err(format!("Failed to fetch releases: {}", resp.status()));
+            }
+            let releases: Vec<GitHubRelease> = resp.json().await.map_err(|e| e.to_string())?;
+            let best = releases
+                .into_iter()
+                .filter(|r| r.tag_name.starts_with("companion-v"))
+                .filter_map(|r| Version::parse(&release_version(&r.tag_name)).map(|v| (v, r)))
+                .max_by(|(a, _), (b, _)| a.cmp(b));
+            match best {
+                Some((_, release)) => release,
+                None => return Ok(None),
+            }
+        }
+    };
+
+    let version_str = release_version(&release.tag_name);
+    if is_newer(current_version, &version_str) {
+        Ok(Some(release))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Substring expected in the release asset name for this build, used to pick the
+/// right platform/arch artifact out of `GitHubRelease::assets`.
+fn platform_asset_name_hint() -> &'static str {
+    #[cfg(all(target_os = "windows", target_arch = "x86_64"))]
+    {
+        "windows-x86_64"
+    }
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    {
+        "linux-x86_64"
+    }
+    #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
+    {
+        "macos-aarch64"
+    }
+    #[cfg(all(target_os = "macos", target_arch = "x86_64"))]
+    {
+        "macos-x86_64"
+    }
+}
+
+fn select_asset(release: &GitHubRelease) -> Option<&GitHubAsset> {
+    let hint = platform_asset_name_hint();
+    release
+        .assets
+        .iter()
+        .find(|a| a.name.contains(hint) && !a.name.ends_with(".sig"))
+}
+
+fn select_sig_asset<'a>(release: &'a GitHubRelease, asset: &GitHubAsset) -> Option<&'a GitHubAsset> {
+    let sig_name = format!("{}.sig", asset.name);
+    release.assets.iter().find(|a| a.name == sig_name)
+}
+
+/// Downloads, verifies, and installs `release`'s platform asset, emitting
+/// `update://download-progress` while streaming and `update://finished`/
+/// `update://error` once the pipeline settles. Refuses to install (returning
+/// `Err` rather than touching disk) if the asset's signature doesn't check out.
+pub async fn download_and_install(app: &AppHandle, state: &AppState, release: &GitHubRelease) -> Result<(), String> {
+    let result = download_and_install_inner(app, state, release).await;
+    match &result {
+        Ok(()) => {
+            let _ = app.emit_all("update://finished", ());
+        }
+        Err(e) => {
+            let _ = app.emit_all("update://error", UpdateError { message: e.clone() });
+        }
+    }
+    result
+}
+
+async fn download_and_install_inner(app: &AppHandle, state: &AppState, release: &GitHubRelease) -> Result<(), String> {
+    let asset = select_asset(release).ok_or_else(|| "no release asset matches this platform".to_string())?;
+    let sig_asset = select_sig_asset(release, asset)
+        .ok_or_else(|| format!("no .sig asset published alongside {}", asset.name))?;
+
+    let client = reqwest::Client::builder()
+        .user_agent("Nojoin-Companion")
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    // Fetch the signature first so a missing/unreachable .sig fails fast, before
+    // spending time streaming the (potentially large) binary asset.
+    let sig_resp = client
+        .get(&sig_asset.browser_download_url)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let sig_text = sig_resp
+        .error_for_status()
+        .map_err(|e| e.to_string())?
+        .text()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let resp = client
+        .get(&asset.browser_download_url)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let resp = resp.error_for_status().map_err(|e| e.to_string())?;
+    let total = resp.content_length();
+
+    let mut artifact = Vec::new();
+    let mut downloaded: u64 = 0;
+    let mut last_emit = std::time::Instant::now() - PROGRESS_THROTTLE;
+    let mut stream = resp.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| e.to_string())?;
+        downloaded += chunk.len() as u64;
+        artifact.extend_from_slice(&chunk);
+
+        if last_emit.elapsed() >= PROGRESS_THROTTLE {
+            last_emit = std::time::Instant::now();
+            emit_progress(app, downloaded, total);
+        }
+    }
+    emit_progress(app, downloaded, total);
+
+    let pubkey = {
+        let config = state.config.lock().unwrap();
+        update_verify::resolve_pubkey(config.pubkey.as_ref())
+    };
+    update_verify::verify_artifact(&artifact, &sig_text, &pubkey)
+        .map_err(|e| format!("downloaded update failed signature verification, refusing to install: {}", e))?;
+
+    let temp_dir = Config::data_dir();
+    std::fs::create_dir_all(&temp_dir).map_err(|e| e.to_string())?;
+    let temp_path = temp_dir.join(format!("{}.download", asset.name));
+    std::fs::write(&temp_path, &artifact).map_err(|e| e.to_string())?;
+
+    // Re-verify against what actually landed on disk, in case the write above was
+    // silently truncated or corrupted.
+    let on_disk = std::fs::read(&temp_path).map_err(|e| e.to_string())?;
+    update_verify::verify_artifact(&on_disk, &sig_text, &pubkey)
+        .map_err(|e| format!("on-disk update failed re-verification, refusing to install: {}", e))?;
+
+    info!("Update artifact verified, installing {}", asset.name);
+    install_downloaded(&temp_path)
+}
+
+fn emit_progress(app: &AppHandle, downloaded: u64, total: Option<u64>) {
+    let percent = total.and_then(|t| if t > 0 { Some(downloaded as f64 / t as f64 * 100.0) } else { None });
+    let _ = app.emit_all(
+        "update://download-progress",
+        DownloadProgress { downloaded, total, percent },
+    );
+}
+
+/// Linux/macOS install: restore the executable bit (lost once the asset was
+/// written out as a plain file) and atomically rename it over the running binary.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn install_downloaded(temp_path: &std::path::Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut perms = std::fs::metadata(temp_path).map_err(|e| e.to_string())?.permissions();
+    perms.set_mode(0o755);
+    std::fs::set_permissions(temp_path, perms).map_err(|e| e.to_string())?;
+
+    let current_exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    std::fs::rename(temp_path, &current_exe).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Windows install: the downloaded asset is an installer, not a drop-in binary
+/// replacement, so launch it instead of renaming over the running executable.
+#[cfg(windows)]
+fn install_downloaded(temp_path: &std::path::Path) -> Result<(), String> {
+    open::that(temp_path).map_err(|e| e.to_string())
+}
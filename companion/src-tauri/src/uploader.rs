@@ -0,0 +1,457 @@
+//! HTTP calls to the Nojoin backend for recording segments, finalization, deletion, and
+//! status updates, drained from a durable `sled`-backed queue so a crash or network blip
+//! can't silently lose in-flight work. Callers enqueue via [`UploadQueue`]; a single
+//! background worker ([`run_queue_worker`]) is responsible for actually talking to the
+//! network and retrying with backoff.
+
+use crate::encoder::SegmentCodec;
+use crate::state::AppState;
+use anyhow::Result;
+use log::{error, info, warn};
+use rand::Rng;
+use reqwest::multipart;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+
+const MAX_ATTEMPTS: u32 = 5;
+const QUEUE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum QueuedAction {
+    UploadSegment { path: PathBuf, codec: SegmentCodec },
+    UploadManifest { body: String },
+    Finalize,
+    Delete,
+    StatusUpdate { status: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct QueueEntry {
+    recording_id: i64,
+    sequence: i32,
+    action: QueuedAction,
+    attempts: u32,
+    last_attempt_at: Option<SystemTime>,
+    last_error: Option<String>,
+}
+
+impl QueueEntry {
+    /// Same backoff schedule as the backend health-check loop (`Config::health_check_*`),
+    /// so a backend that's offline gets one consistent retry cadence across the app
+    /// instead of the queue and the health check independently guessing at a schedule.
+    /// Jittered by up to 20% so a burst of entries that failed together (e.g. the whole
+    /// queue, after a backend outage) don't all retry in the same instant.
+    fn ready(&self, base_secs: f64, max_secs: f64, multiplier: f64) -> bool {
+        match self.last_attempt_at {
+            None => true,
+            Some(last) => {
+                let wait_secs = (base_secs * multiplier.powi(self.attempts as i32)).min(max_secs);
+                let jitter = rand::thread_rng().gen_range(0.0..0.2 * wait_secs);
+                last.elapsed().unwrap_or(Duration::ZERO) >= Duration::from_secs_f64(wait_secs + jitter)
+            }
+        }
+    }
+}
+
+/// Durable queue of pending uploader work. Backed by an embedded `sled` store keyed by
+/// `(recording_id, sequence, kind)` so entries survive a crash and are picked back up by
+/// [`run_queue_worker`] on the next launch instead of being lost.
+pub struct UploadQueue {
+    db: sled::Db,
+}
+
+impl UploadQueue {
+    pub fn open(store_dir: &std::path::Path) -> Result<Self> {
+        std::fs::create_dir_all(store_dir)?;
+        let db = sled::open(store_dir.join("upload_queue.sled"))?;
+        Ok(Self { db })
+    }
+
+    fn store(&self, key: String, entry: &QueueEntry) -> Result<()> {
+        let bytes = serde_json::to_vec(entry)?;
+        self.db.insert(key, bytes)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    pub fn enqueue_upload(
+        &self,
+        recording_id: i64,
+        sequence: i32,
+        path: PathBuf,
+        codec: SegmentCodec,
+    ) -> Result<()> {
+        let key = format!("{}:{}:upload", recording_id, sequence);
+        self.store(
+            key,
+            &QueueEntry {
+                recording_id,
+                sequence,
+                action: QueuedAction::UploadSegment { path, codec },
+                attempts: 0,
+                last_attempt_at: None,
+                last_error: None,
+            },
+        )
+    }
+
+    /// Always the same fixed slot per recording, so a newer rendered playlist simply
+    /// overwrites a still-queued older one instead of the backend replaying a stale
+    /// manifest after catching up from an outage.
+    pub fn enqueue_manifest(&self, recording_id: i64, body: String) -> Result<()> {
+        let key = format!("{}:0:manifest", recording_id);
+        self.store(
+            key,
+            &QueueEntry {
+                recording_id,
+                sequence: 0,
+                action: QueuedAction::UploadManifest { body },
+                attempts: 0,
+                last_attempt_at: None,
+                last_error: None,
+            },
+        )
+    }
+
+    pub fn enqueue_finalize(&self, recording_id: i64) -> Result<()> {
+        let key = format!("{}:0:finalize", recording_id);
+        self.store(
+            key,
+            &QueueEntry {
+                recording_id,
+                sequence: 0,
+                action: QueuedAction::Finalize,
+                attempts: 0,
+                last_attempt_at: None,
+                last_error: None,
+            },
+        )
+    }
+
+    pub fn enqueue_delete(&self, recording_id: i64) -> Result<()> {
+        let key = format!("{}:0:delete", recording_id);
+        self.store(
+            key,
+            &QueueEntry {
+                recording_id,
+                sequence: 0,
+                action: QueuedAction::Delete,
+                attempts: 0,
+                last_attempt_at: None,
+                last_error: None,
+            },
+        )
+    }
+
+    pub fn enqueue_status(&self, recording_id: i64, status: &str) -> Result<()> {
+        // Keying on the status string (rather than a fixed slot) means queued transitions
+        // to different statuses can't clobber each other; a newer transition to the same
+        // status replaces an older one still queued.
+        let key = format!("{}:0:status:{}", recording_id, status);
+        self.store(
+            key,
+            &QueueEntry {
+                recording_id,
+                sequence: 0,
+                action: QueuedAction::StatusUpdate {
+                    status: status.to_string(),
+                },
+                attempts: 0,
+                last_attempt_at: None,
+                last_error: None,
+            },
+        )
+    }
+
+    pub fn depth(&self) -> usize {
+        self.db.len()
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.db
+            .iter()
+            .values()
+            .filter_map(|v| v.ok())
+            .filter_map(|v| serde_json::from_slice::<QueueEntry>(&v).ok())
+            .filter_map(|e| e.last_error)
+            .last()
+    }
+}
+
+/// Drain `state.upload_queue` forever. Entries left over from a previous run are already
+/// in the `sled` store, so this also covers the crash-recovery replay on startup — there
+/// is no separate "resume" step.
+pub async fn run_queue_worker(state: Arc<AppState>) {
+    // Caches the highest sequence each recording's backend has actually received, so a
+    // queue drained after a `BackendOffline` window (or a companion restart) doesn't
+    // re-upload segments the backend already has; refreshed once per recording per pass.
+    let mut synced_sequence: std::collections::HashMap<i64, i32> = std::collections::HashMap::new();
+
+    loop {
+        tokio::time::sleep(QUEUE_POLL_INTERVAL).await;
+
+        let (api_url, base_secs, max_secs, multiplier) = {
+            let config = state.config.lock().unwrap();
+            (
+                config.get_api_url(),
+                config.health_check_base_interval_secs as f64,
+                config.health_check_max_interval_secs as f64,
+                config.health_check_backoff_multiplier,
+            )
+        };
+        let token = match state.valid_token().await {
+            Ok(token) => token,
+            Err(e) => {
+                warn!("Could not obtain a valid backend token, will retry: {}", e);
+                continue;
+            }
+        };
+        let keys: Vec<sled::IVec> = state.upload_queue.db.iter().keys().filter_map(|k| k.ok()).collect();
+        synced_sequence.clear();
+
+        for key in keys {
+            let Some(raw) = state.upload_queue.db.get(&key).ok().flatten() else {
+                continue;
+            };
+            let Ok(mut entry) = serde_json::from_slice::<QueueEntry>(&raw) else {
+                continue;
+            };
+            if !entry.ready(base_secs, max_secs, multiplier) {
+                continue;
+            }
+
+            let result = match &entry.action {
+                QueuedAction::UploadSegment { path, codec } => {
+                    let highest_synced = match synced_sequence.get(&entry.recording_id) {
+                        Some(seq) => Some(*seq),
+                        None => {
+                            let fetched =
+                                query_highest_synced_sequence(entry.recording_id, &api_url, &token)
+                                    .await
+                                    .unwrap_or(None);
+                            if let Some(seq) = fetched {
+                                synced_sequence.insert(entry.recording_id, seq);
+                            }
+                            fetched
+                        }
+                    };
+
+                    if highest_synced.map_or(false, |synced| entry.sequence <= synced) {
+                        info!(
+                            "Segment {} for recording {} already synced (backend has through {}), skipping",
+                            entry.sequence, entry.recording_id, highest_synced.unwrap()
+                        );
+                        Ok(())
+                    } else {
+                        if entry.attempts > 0 {
+                            state.upload_retries_total.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                        }
+                        do_upload_segment(entry.recording_id, entry.sequence, path, *codec, &api_url, &token).await
+                    }
+                }
+                QueuedAction::UploadManifest { body } => {
+                    do_upload_manifest(entry.recording_id, body, &api_url, &token).await
+                }
+                QueuedAction::Finalize => {
+                    do_finalize_recording(entry.recording_id, &api_url, &token).await
+                }
+                QueuedAction::Delete => do_delete_recording(entry.recording_id, &api_url, &token).await,
+                QueuedAction::StatusUpdate { status } => {
+                    do_update_client_status(entry.recording_id, status, &api_url, &token).await
+                }
+            };
+
+            match result {
+                Ok(()) => {
+                    let _ = state.upload_queue.db.remove(&key);
+                    let _ = state.upload_queue.db.flush();
+                }
+                Err(e) => {
+                    entry.attempts += 1;
+                    entry.last_attempt_at = Some(SystemTime::now());
+                    entry.last_error = Some(e.to_string());
+
+                    if entry.attempts >= MAX_ATTEMPTS {
+                        error!(
+                            "Queued {:?} for recording {} still failing after {} attempts, keeping it queued: {}",
+                            entry.action, entry.recording_id, entry.attempts, e
+                        );
+                    } else {
+                        warn!(
+                            "Queued {:?} for recording {} failed (attempt {}/{}): {}",
+                            entry.action, entry.recording_id, entry.attempts, MAX_ATTEMPTS, e
+                        );
+                    }
+
+                    if let Ok(bytes) = serde_json::to_vec(&entry) {
+                        let _ = state.upload_queue.db.insert(&key, bytes);
+                        let _ = state.upload_queue.db.flush();
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Asks the backend for the highest segment sequence it has durably received for a
+/// recording, so the queue worker knows which already-queued uploads it can skip.
+/// `Ok(None)` means the backend doesn't have anything for this recording yet (or is
+/// too old to support the endpoint) — callers should fall back to uploading everything.
+async fn query_highest_synced_sequence(recording_id: i64, api_url: &str, token: &str) -> Result<Option<i32>> {
+    let client = reqwest::Client::new();
+    let url = format!("{}/recordings/{}/segments/highest", api_url, recording_id);
+    let response = client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        return Ok(None);
+    }
+
+    #[derive(Deserialize)]
+    struct HighestSequenceResponse {
+        highest_sequence: Option<i32>,
+    }
+    let parsed: HighestSequenceResponse = response.json().await?;
+    Ok(parsed.highest_sequence)
+}
+
+async fn do_upload_segment(
+    recording_id: i64,
+    sequence: i32,
+    file_path: &PathBuf,
+    codec: SegmentCodec,
+    api_url: &str,
+    token: &str,
+) -> Result<()> {
+    let client = reqwest::Client::new();
+    let mut file = File::open(file_path).await?;
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents).await?;
+    let total_bytes = contents.len();
+
+    let part = multipart::Part::bytes(contents).file_name(format!("segment.{}", codec.extension()));
+    let form = multipart::Form::new().part("file", part);
+    let url = format!(
+        "{}/recordings/{}/segment?sequence={}&codec={}",
+        api_url,
+        recording_id,
+        sequence,
+        codec.extension()
+    );
+
+    client
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", token))
+        // Byte range of this segment's payload; the sequence query param is what the
+        // backend actually keys storage on, this just tells it how many bytes to expect.
+        .header("Content-Range", format!("bytes 0-{}/{}", total_bytes.saturating_sub(1), total_bytes))
+        .multipart(form)
+        .send()
+        .await?
+        .error_for_status()
+        .map_err(|e| anyhow::anyhow!("Upload failed: {}", e))?;
+
+    Ok(())
+}
+
+async fn do_upload_manifest(recording_id: i64, body: &str, api_url: &str, token: &str) -> Result<()> {
+    let client = reqwest::Client::new();
+    let url = format!("{}/recordings/{}/manifest", api_url, recording_id);
+    client
+        .put(&url)
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Type", "application/vnd.apple.mpegurl")
+        .body(body.to_string())
+        .send()
+        .await?
+        .error_for_status()
+        .map_err(|e| anyhow::anyhow!("Manifest upload failed: {}", e))?;
+
+    Ok(())
+}
+
+async fn do_finalize_recording(recording_id: i64, api_url: &str, token: &str) -> Result<()> {
+    let client = reqwest::Client::new();
+    let url = format!("{}/recordings/{}/finalize", api_url, recording_id);
+    client
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await?
+        .error_for_status()
+        .map_err(|e| anyhow::anyhow!("Finalize failed: {}", e))?;
+
+    Ok(())
+}
+
+async fn do_delete_recording(recording_id: i64, api_url: &str, token: &str) -> Result<()> {
+    let client = reqwest::Client::new();
+    let url = format!("{}/recordings/{}", api_url, recording_id);
+    client
+        .delete(&url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await?
+        .error_for_status()
+        .map_err(|e| anyhow::anyhow!("Delete failed: {}", e))?;
+
+    Ok(())
+}
+
+async fn do_update_client_status(recording_id: i64, status: &str, api_url: &str, token: &str) -> Result<()> {
+    let client = reqwest::Client::new();
+    let url = format!("{}/recordings/{}/status", api_url, recording_id);
+    client
+        .put(&url)
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&serde_json::json!({ "status": status }))
+        .send()
+        .await?
+        .error_for_status()
+        .map_err(|e| anyhow::anyhow!("Status update failed: {}", e))?;
+
+    Ok(())
+}
+
+/// Queue a segment upload. Returns once the entry is durably recorded, not once it's
+/// actually delivered — [`run_queue_worker`] handles the real network call and retries.
+pub fn upload_segment(
+    recording_id: i64,
+    sequence: i32,
+    path: &std::path::Path,
+    codec: SegmentCodec,
+    state: &AppState,
+) -> Result<()> {
+    state
+        .upload_queue
+        .enqueue_upload(recording_id, sequence, path.to_path_buf(), codec)?;
+    info!("Queued segment {} for recording {}", sequence, recording_id);
+    Ok(())
+}
+
+/// Queue the current rendered playlist for upload, overwriting whatever manifest
+/// upload for this recording is still sitting in the queue. Same fire-and-forget
+/// contract as [`upload_segment`] — durably recorded here, actually delivered by
+/// [`run_queue_worker`].
+pub fn upload_manifest(recording_id: i64, body: String, state: &AppState) -> Result<()> {
+    state.upload_queue.enqueue_manifest(recording_id, body)
+}
+
+pub fn finalize_recording(recording_id: i64, state: &AppState) -> Result<()> {
+    state.upload_queue.enqueue_finalize(recording_id)
+}
+
+pub fn delete_recording(recording_id: i64, state: &AppState) -> Result<()> {
+    state.upload_queue.enqueue_delete(recording_id)
+}
+
+pub fn update_client_status(recording_id: i64, status: &str, state: &AppState) -> Result<()> {
+    state.upload_queue.enqueue_status(recording_id, status)
+}
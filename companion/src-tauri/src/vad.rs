@@ -0,0 +1,167 @@
+//! FFT-based voice-activity detection over the mixed mono stream. Buffers
+//! `FRAME_MS` frames, runs a real FFT, sums spectral energy in the speech band
+//! (~300-3400 Hz), and compares it against an adaptive noise floor (an
+//! exponential moving average of recent quiet frames). Frames flip
+//! speech/silence with hysteresis so `audio::run_mixing_loop` can trim dead air
+//! and prefer splitting segments at a detected pause instead of mid-word,
+//! without chattering on every loud breath or short gap.
+
+use realfft::RealFftPlanner;
+use std::sync::Arc;
+
+/// Frame size in milliseconds: long enough for useful frequency resolution in
+/// the speech band, short enough to classify a pause quickly.
+const FRAME_MS: u32 = 30;
+/// Speech formant energy mostly lives in this band; content outside it (room
+/// rumble, hiss) is ignored so it doesn't drag the floor/ratio around.
+const SPEECH_BAND_HZ: (f32, f32) = (300.0, 3400.0);
+/// Consecutive speech frames required to open (start of an utterance) and
+/// consecutive silence frames required to close (end of one). Opening faster
+/// than it closes avoids clipping the start of a word while still not ending a
+/// sentence on a single short breath-pause.
+const OPEN_FRAMES: u32 = 2;
+const CLOSE_FRAMES: u32 = 10;
+/// A frame's speech-band energy must exceed the noise floor by this multiple to
+/// be considered voiced.
+const SPEECH_RATIO_THRESHOLD: f32 = 2.5;
+/// How much a single quiet frame is allowed to pull the noise floor down by, so
+/// the floor tracks a slowly rising room tone without being yanked around by one
+/// unusually quiet frame.
+const NOISE_FLOOR_RISE: f32 = 1.01;
+const NOISE_FLOOR_FALL: f32 = 0.95;
+
+/// Per-frame verdict handed back to the caller.
+#[derive(Clone, Copy, Debug)]
+pub struct VadFrame {
+    pub is_speech: bool,
+    /// Speech-band energy for this frame, scaled 0-100 like the existing RMS
+    /// level meters, for `AppState::speech_energy`/the UI's VAD indicator.
+    pub speech_energy: u32,
+}
+
+/// Runs entirely synchronously inside `audio::run_mixing_loop` — unlike
+/// `transcribe::spawn`, there's no off-thread channel here, since the mixing
+/// loop needs each frame's verdict immediately to decide whether to write or
+/// trim the samples that produced it.
+pub struct VoiceActivityDetector {
+    sample_rate: u32,
+    frame_len: usize,
+    pending: Vec<f32>,
+    fft: Arc<dyn realfft::RealToComplex<f32>>,
+    spectrum: Vec<rustfft::num_complex::Complex<f32>>,
+    window: Vec<f32>,
+    band_lo_bin: usize,
+    band_hi_bin: usize,
+    noise_floor: f32,
+    speech_frames: u32,
+    silence_frames: u32,
+    is_speech: bool,
+}
+
+impl VoiceActivityDetector {
+    pub fn new(sample_rate: u32) -> Self {
+        let frame_len = ((sample_rate as u64 * FRAME_MS as u64) / 1000) as usize;
+        let mut planner = RealFftPlanner::<f32>::new();
+        let fft = planner.plan_fft_forward(frame_len);
+        let spectrum = fft.make_output_vec();
+
+        // Hann window to tame spectral leakage from the hard frame edges.
+        let window: Vec<f32> = (0..frame_len)
+            .map(|i| {
+                0.5 * (1.0
+                    - (2.0 * std::f32::consts::PI * i as f32 / (frame_len.max(2) - 1) as f32).cos())
+            })
+            .collect();
+
+        let bin_hz = sample_rate as f32 / frame_len as f32;
+        let band_lo_bin = (SPEECH_BAND_HZ.0 / bin_hz).floor() as usize;
+        let band_hi_bin = ((SPEECH_BAND_HZ.1 / bin_hz).ceil() as usize).min(spectrum.len().saturating_sub(1));
+
+        VoiceActivityDetector {
+            sample_rate,
+            frame_len,
+            pending: Vec::with_capacity(frame_len * 2),
+            fft,
+            spectrum,
+            window,
+            band_lo_bin,
+            band_hi_bin,
+            // Seed slightly above zero so an all-silence opener doesn't divide by
+            // zero and immediately classify as speech.
+            noise_floor: 1e-4,
+            speech_frames: 0,
+            silence_frames: 0,
+            is_speech: false,
+        }
+    }
+
+    /// Buffers `samples` and classifies every complete `FRAME_MS` frame that
+    /// becomes available. A mixing-loop callback's chunk rarely lines up with
+    /// the frame size, so this can return zero, one, or several frames.
+    pub fn process(&mut self, samples: &[f32]) -> Vec<VadFrame> {
+        self.pending.extend_from_slice(samples);
+
+        let mut results = Vec::new();
+        while self.pending.len() >= self.frame_len {
+            let frame: Vec<f32> = self.pending.drain(0..self.frame_len).collect();
+            results.push(self.classify_frame(&frame));
+        }
+        results
+    }
+
+    fn classify_frame(&mut self, frame: &[f32]) -> VadFrame {
+        let mut windowed: Vec<f32> = frame
+            .iter()
+            .zip(self.window.iter())
+            .map(|(s, w)| s * w)
+            .collect();
+
+        if self.fft.process(&mut windowed, &mut self.spectrum).is_err() {
+            // Shouldn't happen with a correctly-sized buffer; treat as silence
+            // rather than propagating a DSP error up into the mixing loop.
+            return VadFrame { is_speech: false, speech_energy: 0 };
+        }
+
+        let band_energy: f32 = self.spectrum[self.band_lo_bin..=self.band_hi_bin]
+            .iter()
+            .map(|c| c.norm_sqr())
+            .sum();
+
+        let is_speech_candidate = band_energy > self.noise_floor * SPEECH_RATIO_THRESHOLD;
+
+        if is_speech_candidate {
+            self.speech_frames += 1;
+            self.silence_frames = 0;
+        } else {
+            self.silence_frames += 1;
+            self.speech_frames = 0;
+            // Only non-speech frames move the floor, so a loud frame inside an
+            // utterance doesn't drag it upward.
+            self.noise_floor = if band_energy < self.noise_floor {
+                self.noise_floor * NOISE_FLOOR_FALL + band_energy * (1.0 - NOISE_FLOOR_FALL)
+            } else {
+                self.noise_floor * NOISE_FLOOR_RISE
+            };
+        }
+
+        if !self.is_speech && self.speech_frames >= OPEN_FRAMES {
+            self.is_speech = true;
+        } else if self.is_speech && self.silence_frames >= CLOSE_FRAMES {
+            self.is_speech = false;
+        }
+
+        let speech_energy = ((band_energy / (self.noise_floor * SPEECH_RATIO_THRESHOLD)).min(1.0) * 100.0) as u32;
+
+        VadFrame { is_speech: self.is_speech, speech_energy }
+    }
+
+    /// Frame size in samples at this detector's sample rate, for callers sizing
+    /// silence-run thresholds in samples rather than milliseconds.
+    pub fn frame_len(&self) -> usize {
+        self.frame_len
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
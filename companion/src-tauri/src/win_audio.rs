@@ -0,0 +1,247 @@
+//! Windows WASAPI loopback capture of the default render (system audio) device,
+//! the Windows counterpart to `mac_sc`'s ScreenCaptureKit capture. There's no
+//! permission prompt to trigger here the way ScreenCaptureKit has one, so
+//! `check_permissions` is a best-effort "can we even enumerate a render
+//! device" probe rather than anything that actually grants access.
+
+use crossbeam_channel::Sender;
+use log::{error, info};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use windows::Win32::Media::Audio::{
+    eConsole, eRender, IAudioCaptureClient, IAudioClient, IMMDeviceEnumerator, MMDeviceEnumerator,
+    AUDCLNT_BUFFERFLAGS_SILENT, AUDCLNT_SHAREMODE_SHARED, AUDCLNT_STREAMFLAGS_EVENTCALLBACK,
+    AUDCLNT_STREAMFLAGS_LOOPBACK, WAVEFORMATEX, WAVEFORMATEXTENSIBLE,
+};
+use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_ALL, COINIT_MULTITHREADED};
+use windows::Win32::System::Threading::{CreateEventA, WaitForSingleObject, INFINITE};
+use windows::Win32::Foundation::WAIT_OBJECT_0;
+
+/// Owns the capture engine thread; dropping it signals the loop to stop and
+/// joins it, same shape as `mac_sc::AudioCaptureStream`.
+pub struct AudioCaptureStream {
+    stop: Arc<AtomicBool>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl Drop for AudioCaptureStream {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.thread.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Start capturing the default render device in loopback mode, converting to
+/// mono and resampling to `sample_rate` before sending on `tx`. `channels` is
+/// accepted for parity with `mac_sc::start_capture` but unused: the device's
+/// actual channel count comes from its mix format, not the caller.
+pub fn start_capture(tx: Sender<Vec<f32>>, sample_rate: u32, _channels: u16) -> Result<AudioCaptureStream, String> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_thread = stop.clone();
+    let (ready_tx, ready_rx) = mpsc::channel::<Result<(), String>>();
+
+    let thread = std::thread::spawn(move || {
+        if let Err(e) = run_capture(tx, sample_rate, stop_thread, &ready_tx) {
+            let _ = ready_tx.send(Err(e));
+        }
+    });
+
+    match ready_rx.recv() {
+        Ok(Ok(())) => Ok(AudioCaptureStream { stop, thread: Some(thread) }),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err("capture thread exited before signaling readiness".to_string()),
+    }
+}
+
+/// Runs entirely on its own thread: COM apartments and `IAudioClient` objects
+/// are not `Send`, so setup and the capture loop both have to live here
+/// rather than being split across the caller and a spawned closure.
+fn run_capture(
+    tx: Sender<Vec<f32>>,
+    target_sample_rate: u32,
+    stop: Arc<AtomicBool>,
+    ready_tx: &mpsc::Sender<Result<(), String>>,
+) -> Result<(), String> {
+    unsafe {
+        CoInitializeEx(None, COINIT_MULTITHREADED).ok().map_err(|e| e.to_string())?;
+
+        let enumerator: IMMDeviceEnumerator =
+            CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL).map_err(|e| e.to_string())?;
+        let device = enumerator
+            .GetDefaultAudioEndpoint(eRender, eConsole)
+            .map_err(|e| e.to_string())?;
+
+        let audio_client: IAudioClient = device.Activate(CLSCTX_ALL, None).map_err(|e| e.to_string())?;
+        let mix_format = audio_client.GetMixFormat().map_err(|e| e.to_string())?;
+        let (channels, device_sample_rate) = mix_format_channels_and_rate(mix_format);
+
+        audio_client
+            .Initialize(
+                AUDCLNT_SHAREMODE_SHARED,
+                AUDCLNT_STREAMFLAGS_LOOPBACK | AUDCLNT_STREAMFLAGS_EVENTCALLBACK,
+                0,
+                0,
+                mix_format,
+                None,
+            )
+            .map_err(|e| e.to_string())?;
+
+        let event_handle = CreateEventA(None, false, false, None).map_err(|e| e.to_string())?;
+        audio_client.SetEventHandle(event_handle).map_err(|e| e.to_string())?;
+
+        let capture_client: IAudioCaptureClient = audio_client.GetService().map_err(|e| e.to_string())?;
+        audio_client.Start().map_err(|e| e.to_string())?;
+        info!(
+            "Windows loopback capture started: {}ch, {}Hz -> resampling to {}Hz",
+            channels, device_sample_rate, target_sample_rate
+        );
+
+        let _ = ready_tx.send(Ok(()));
+
+        let mut resampler = LinearResampler::new(target_sample_rate as f64 / device_sample_rate as f64);
+
+        while !stop.load(Ordering::SeqCst) {
+            if WaitForSingleObject(event_handle, 200) != WAIT_OBJECT_0 {
+                continue;
+            }
+
+            loop {
+                let mut packet_frames = capture_client.GetNextPacketSize().map_err(|e| e.to_string())?;
+                if packet_frames == 0 {
+                    break;
+                }
+
+                while packet_frames > 0 {
+                    let mut data_ptr = std::ptr::null_mut();
+                    let mut frames_available = 0u32;
+                    let mut flags = 0u32;
+                    capture_client
+                        .GetBuffer(&mut data_ptr, &mut frames_available, &mut flags, None, None)
+                        .map_err(|e| e.to_string())?;
+
+                    let silent = flags & AUDCLNT_BUFFERFLAGS_SILENT.0 as u32 != 0;
+                    let frame_samples = if silent || data_ptr.is_null() {
+                        vec![0.0f32; frames_available as usize * channels as usize]
+                    } else {
+                        std::slice::from_raw_parts(data_ptr as *const f32, frames_available as usize * channels as usize).to_vec()
+                    };
+
+                    capture_client
+                        .ReleaseBuffer(frames_available)
+                        .map_err(|e| e.to_string())?;
+
+                    let mono = to_mono(&frame_samples, channels);
+                    let mut resampled = Vec::new();
+                    resampler.process(&mono, &mut resampled);
+                    if !resampled.is_empty() {
+                        if let Err(e) = tx.send(resampled) {
+                            error!("Failed to send loopback samples: {}", e);
+                        }
+                    }
+
+                    packet_frames = capture_client.GetNextPacketSize().map_err(|e| e.to_string())?;
+                }
+            }
+        }
+
+        audio_client.Stop().map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// `mix_format` is `*mut WAVEFORMATEX`, usually really a `WAVEFORMATEXTENSIBLE`
+/// when the device reports more than 2 channels or a non-integer format; read
+/// through the extensible view whenever the tag says to.
+unsafe fn mix_format_channels_and_rate(mix_format: *mut WAVEFORMATEX) -> (u16, u32) {
+    const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+    let base = &*mix_format;
+    if base.wFormatTag == WAVE_FORMAT_EXTENSIBLE {
+        let ext = &*(mix_format as *const WAVEFORMATEXTENSIBLE);
+        (ext.Format.nChannels, ext.Format.nSamplesPerSec)
+    } else {
+        (base.nChannels, base.nSamplesPerSec)
+    }
+}
+
+/// Interleaved multi-channel f32 -> mono, averaging channels exactly like
+/// `mac_sc::extract_audio_samples` does for ScreenCaptureKit.
+fn to_mono(data: &[f32], channels: u16) -> Vec<f32> {
+    if channels <= 1 {
+        return data.to_vec();
+    }
+    data.chunks(channels as usize)
+        .map(|chunk| chunk.iter().sum::<f32>() / channels as f32)
+        .collect()
+}
+
+/// Naive linear-interpolation resampler. Good enough for matching the mic's
+/// sample rate before mixing; not a substitute for a proper polyphase filter.
+/// Carries its fractional position and the last sample of the previous call
+/// across packet boundaries (like `encoder::LinearResampler` and
+/// `audio::next_resampled_sys_sample`), so interpolation runs smoothly through
+/// a packet boundary instead of truncating the remainder and clicking every
+/// ~10ms of WASAPI loopback audio.
+struct LinearResampler {
+    ratio: f64, // output rate / input rate
+    pos: f64,
+    carry: f32,
+}
+
+impl LinearResampler {
+    fn new(ratio: f64) -> Self {
+        Self {
+            ratio,
+            pos: 0.0,
+            carry: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: &[f32], out: &mut Vec<f32>) {
+        if input.is_empty() {
+            return;
+        }
+        let n = input.len();
+        let step = 1.0 / self.ratio;
+        let at = |i: usize, carry: f32| -> f32 {
+            if i == 0 {
+                carry
+            } else {
+                input[i - 1]
+            }
+        };
+        while self.pos < n as f64 {
+            let idx = self.pos as usize;
+            let frac = (self.pos - idx as f64) as f32;
+            let s0 = at(idx, self.carry);
+            let s1 = at(idx + 1, self.carry);
+            out.push(s0 + (s1 - s0) * frac);
+            self.pos += step;
+        }
+        self.pos -= n as f64;
+        self.carry = input[n - 1];
+    }
+}
+
+/// Best-effort probe: there's no explicit consent prompt for loopback capture
+/// on Windows, so this just confirms a default render device is enumerable.
+pub fn check_permissions() -> bool {
+    unsafe {
+        if CoInitializeEx(None, COINIT_MULTITHREADED).is_err() {
+            // Already initialized on this thread with different concurrency
+            // model is not fatal; only a hard failure to enumerate is.
+        }
+        let enumerator: windows::core::Result<IMMDeviceEnumerator> =
+            CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL);
+        match enumerator {
+            Ok(enumerator) => enumerator.GetDefaultAudioEndpoint(eRender, eConsole).is_ok(),
+            Err(e) => {
+                error!("Failed to enumerate render devices: {}", e);
+                false
+            }
+        }
+    }
+}
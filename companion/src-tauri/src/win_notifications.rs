@@ -1,4 +1,8 @@
 #[cfg(windows)]
+use crate::config::Config;
+#[cfg(windows)]
+use crate::update_verify;
+#[cfg(windows)]
 use log::error;
 #[cfg(windows)]
 use tauri::{AppHandle, Manager};
@@ -8,13 +12,21 @@ use tauri_plugin_updater::UpdaterExt;
 use win32_notif::{
     notification::{
         actions::{action::ActivationType, ActionButton},
-        visual::Text,
+        visual::{progress_bar::ProgressBar, Text},
     },
-    NotificationActivatedEventHandler, NotificationBuilder, ToastsNotifier,
+    NotificationActivatedEventHandler, NotificationBuilder, NotificationData, ToastsNotifier,
 };
 
+/// Tag/group used to target the progress toast with `NotificationData` updates.
+/// Windows addresses an existing toast by this pair rather than by handle, so
+/// both the initial `build`/`show` and every later `update` must agree on them.
+#[cfg(windows)]
+const PROGRESS_TAG: &str = "update-progress";
+#[cfg(windows)]
+const PROGRESS_GROUP: &str = "updates";
+
 #[cfg(windows)]
-pub fn show_update_notification(app: AppHandle, version: String) {
+pub fn show_update_notification(app: AppHandle, version: String, _url: String, config: Config) {
     // The App ID must match what is registered by the installer or the executable
     // Tauri usually uses the bundle identifier.
     let app_id = "com.valtora.nojoin.companion";
@@ -27,6 +39,7 @@ pub fn show_update_notification(app: AppHandle, version: String) {
     let notifier = notifier_result.unwrap();
 
     let app_handle = app.clone();
+    let config_for_update = config.clone();
 
     let notif_result = NotificationBuilder::new()
         .visual(Text::create(0, "Update Available"))
@@ -50,6 +63,7 @@ pub fn show_update_notification(app: AppHandle, version: String) {
                     if let Some(id) = &args.button_id {
                         if id == "update" {
                             let app = app_handle.clone();
+                            let config = config_for_update.clone();
                             tauri::async_runtime::spawn(async move {
                                 // Logic to install update
                                 let updater = match app.updater() {
@@ -62,9 +76,100 @@ pub fn show_update_notification(app: AppHandle, version: String) {
 
                                 match updater.check().await {
                                     Ok(Some(update)) => {
-                                        // Show a notification that we are updating?
-                                        // Or just do it.
-                                        if let Err(e) = update.download_and_install(|_, _| {}, || {}).await {
+                                        // Download the raw artifact ourselves (instead of
+                                        // `download_and_install`) so it can be checked against
+                                        // the detached minisign signature before anything
+                                        // touches disk. Progress is shown via a toast whose
+                                        // progress bar is bound to data keys, updated in place
+                                        // through `NotificationData` rather than re-showing a
+                                        // toast per chunk.
+                                        let notifier_for_progress = match ToastsNotifier::new(app_id) {
+                                            Ok(n) => Some(n),
+                                            Err(e) => {
+                                                error!("Failed to create progress ToastsNotifier: {:?}", e);
+                                                None
+                                            }
+                                        };
+                                        if let Some(progress_notifier) = notifier_for_progress.as_ref() {
+                                            let progress_notif = NotificationBuilder::new()
+                                                .visual(Text::create(0, "Updating Nojoin Companion"))
+                                                .visual(
+                                                    ProgressBar::create(1, "progressStatus", 0.0)
+                                                        .with_value_string_bind("progressValue"),
+                                                )
+                                                .build(1, progress_notifier, PROGRESS_TAG, PROGRESS_GROUP);
+                                            if let Ok(notif) = progress_notif {
+                                                if let Err(e) = notif.show() {
+                                                    error!("Failed to show progress notification: {:?}", e);
+                                                }
+                                            }
+                                        }
+
+                                        let mut downloaded: u64 = 0;
+                                        let artifact = match update
+                                            .download(
+                                                |chunk_len, total_len| {
+                                                    downloaded += chunk_len as u64;
+                                                    let Some(progress_notifier) = notifier_for_progress.as_ref() else {
+                                                        return;
+                                                    };
+                                                    let (value, status) = match total_len {
+                                                        Some(total) if total > 0 => (
+                                                            (downloaded as f64 / total as f64).min(1.0),
+                                                            format!("{}%", (downloaded * 100 / total).min(100)),
+                                                        ),
+                                                        _ => (0.0, format!("{} KB", downloaded / 1024)),
+                                                    };
+                                                    let data = NotificationData::new()
+                                                        .set("progressValue", &status)
+                                                        .set_progress(1, value);
+                                                    if let Err(e) =
+                                                        progress_notifier.update(&data, PROGRESS_TAG, PROGRESS_GROUP)
+                                                    {
+                                                        error!("Failed to update progress notification: {:?}", e);
+                                                    }
+                                                },
+                                                || {},
+                                            )
+                                            .await
+                                        {
+                                            Ok(bytes) => bytes,
+                                            Err(e) => {
+                                                error!("Failed to download update: {}", e);
+                                                return;
+                                            }
+                                        };
+                                        if let Some(progress_notifier) = notifier_for_progress.as_ref() {
+                                            let _ = progress_notifier.remove(PROGRESS_TAG, PROGRESS_GROUP);
+                                        }
+
+                                        let sig_url = format!("{}.sig", update.download_url);
+                                        let sig_text = match reqwest::get(&sig_url).await.and_then(|r| r.error_for_status()) {
+                                            Ok(resp) => match resp.text().await {
+                                                Ok(text) => text,
+                                                Err(e) => {
+                                                    error!("Failed to read update signature: {}", e);
+                                                    return;
+                                                }
+                                            },
+                                            Err(e) => {
+                                                error!("Failed to download update signature from {}: {}", sig_url, e);
+                                                return;
+                                            }
+                                        };
+
+                                        let pubkey = update_verify::resolve_pubkey(config.pubkey.as_ref());
+                                        if let Err(e) = update_verify::verify_artifact(&artifact, &sig_text, &pubkey) {
+                                            error!("Update signature verification failed, refusing to install: {}", e);
+                                            crate::notifications::show_notification(
+                                                &app,
+                                                "Update Failed",
+                                                "The downloaded update's signature could not be verified. Installation was aborted.",
+                                            );
+                                            return;
+                                        }
+
+                                        if let Err(e) = update.install(artifact) {
                                             error!("Failed to install update: {}", e);
                                         } else {
                                             app.restart();
@@ -1,13 +1,220 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::Device;
 use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use crossbeam_channel::Receiver;
-use crate::state::{AppState, AudioCommand, AppStatus};
+use crate::state::{AppState, AudioCommand, AppStatus, ChunkHeader, StreamOrigin};
 use crate::uploader;
-use crate::config::Config;
+use crate::config::{Config, SegmentCodec, RecordingMode};
+use crate::opus_codec::OpusSegmentWriter;
+use crate::notifications;
+use crate::upload_queue;
 use std::thread;
 use hound;
 
+/// Target bitrate for Opus segments; sits in the recommended range for speech.
+const OPUS_BITRATE_BPS: i32 = 28_000;
+
+/// Abstracts over the two segment file formats so the mixing loop doesn't need to
+/// know which one it's writing to.
+enum SegmentSink {
+    Wav(hound::WavWriter<std::io::BufWriter<std::fs::File>>),
+    Opus {
+        writer: OpusSegmentWriter<std::fs::File>,
+        resampler: LinearResampler,
+    },
+    /// Dual-track mode: interleaved stereo WAV, channel 0 mic / channel 1 system
+    /// audio, both already on the mic's master clock. Opus isn't wired up for this
+    /// mode since nothing downstream needs dual-track bandwidth savings yet.
+    WavDual(hound::WavWriter<std::io::BufWriter<std::fs::File>>),
+}
+
+impl SegmentSink {
+    fn write_mixed(&mut self, mixed: &[f32]) {
+        match self {
+            SegmentSink::Wav(writer) => {
+                let amplitude = i16::MAX as f32;
+                for &sample in mixed {
+                    writer.write_sample((sample * amplitude) as i16).unwrap();
+                }
+            }
+            SegmentSink::Opus { writer, resampler } => {
+                let mut resampled = Vec::new();
+                resampler.process(mixed, &mut resampled);
+                if let Err(e) = writer.push(&resampled) {
+                    eprintln!("Opus encode error: {}", e);
+                }
+            }
+            SegmentSink::WavDual(_) => unreachable!("mixed writes never target a dual-track sink"),
+        }
+    }
+
+    /// Writes one mic/system-audio pair per index as interleaved stereo samples.
+    /// `mic` and `sys` must be the same length; callers zero-pad `sys` to match.
+    fn write_dual(&mut self, mic: &[f32], sys: &[f32]) {
+        match self {
+            SegmentSink::WavDual(writer) => {
+                let amplitude = i16::MAX as f32;
+                for (&mic_sample, &sys_sample) in mic.iter().zip(sys.iter()) {
+                    writer.write_sample((mic_sample.clamp(-1.0, 1.0) * amplitude) as i16).unwrap();
+                    writer.write_sample((sys_sample.clamp(-1.0, 1.0) * amplitude) as i16).unwrap();
+                }
+            }
+            _ => unreachable!("dual-track writes never target a mixed sink"),
+        }
+    }
+
+    fn finalize(self) {
+        match self {
+            SegmentSink::Wav(writer) | SegmentSink::WavDual(writer) => {
+                writer.finalize().unwrap();
+            }
+            SegmentSink::Opus { writer, .. } => {
+                if let Err(e) = writer.finalize() {
+                    eprintln!("Opus finalize error: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Latches a fresh wall-clock/monotonic origin in `state`. Sample counters are only
+/// zeroed when `reset_counters` is set (a brand-new recording), so a resume-after-pause
+/// keeps counting from where it left off and the gap shows up in the next header.
+fn latch_stream_origin(state: &Arc<AppState>, reset_counters: bool) {
+    if reset_counters {
+        state.mic_samples_total.store(0, Ordering::SeqCst);
+        state.sys_samples_total.store(0, Ordering::SeqCst);
+    }
+    *state.stream_origin.lock().unwrap() = Some(StreamOrigin {
+        instant: Instant::now(),
+        unix_time: SystemTime::now(),
+    });
+}
+
+/// Which side of the mic/loopback pair a capture stream feeds; lets one generic
+/// stream builder drive both without duplicating the conversion/level/send logic
+/// per sample format.
+#[derive(Clone, Copy)]
+enum StreamRole {
+    Mic,
+    Sys,
+}
+
+fn i16_to_f32(sample: i16) -> f32 {
+    sample as f32 / 32768.0
+}
+
+fn u16_to_f32(sample: u16) -> f32 {
+    (sample as f32 - 32768.0) / 32768.0
+}
+
+fn f32_identity(sample: f32) -> f32 {
+    sample
+}
+
+// Helper to convert interleaved to mono
+fn to_mono(data: &[f32], channels: u16) -> Vec<f32> {
+    if channels == 1 {
+        return data.to_vec();
+    }
+    let mut mono = Vec::with_capacity(data.len() / channels as usize);
+    for chunk in data.chunks(channels as usize) {
+        let sum: f32 = chunk.iter().sum();
+        mono.push(sum / channels as f32);
+    }
+    mono
+}
+
+// Helper to calculate RMS level (0.0 to 1.0)
+fn calculate_rms(data: &[f32]) -> f32 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    let sum_squares: f32 = data.iter().map(|s| s * s).sum();
+    (sum_squares / data.len() as f32).sqrt()
+}
+
+/// Linearly resamples the system-audio stream onto the mic's sample clock so the two
+/// stay aligned over a long recording instead of slowly drifting apart. `pos` is a
+/// fractional cursor into the incoming chunk, prefixed with the previous chunk's last
+/// sample so interpolation is continuous across chunk boundaries (no clicks at seams).
+struct LinearResampler {
+    ratio: f64, // output rate / input rate
+    pos: f64,
+    carry: f32,
+}
+
+impl LinearResampler {
+    fn new(ratio: f64) -> Self {
+        Self { ratio, pos: 0.0, carry: 0.0 }
+    }
+
+    fn process(&mut self, input: &[f32], out: &mut Vec<f32>) {
+        if input.is_empty() {
+            return;
+        }
+        let n = input.len();
+        let step = 1.0 / self.ratio;
+        let at = |i: usize, carry: f32| -> f32 {
+            if i == 0 { carry } else { input[i - 1] }
+        };
+        while self.pos < n as f64 {
+            let idx = self.pos as usize;
+            let frac = (self.pos - idx as f64) as f32;
+            let s0 = at(idx, self.carry);
+            let s1 = at(idx + 1, self.carry);
+            out.push(s0 + (s1 - s0) * frac);
+            self.pos += step;
+        }
+        self.pos -= n as f64;
+        self.carry = input[n - 1];
+    }
+}
+
+/// Builds an input stream for sample type `T`, converting every sample to `f32` with
+/// `convert` before feeding it through the existing mono/RMS/mix path. Following
+/// cpal's sample-format model, callers match on `SupportedStreamConfig::sample_format()`
+/// and instantiate this once per `I16`/`U16`/`F32` variant so devices that don't
+/// natively expose f32 (common on WASAPI/ALSA) don't panic `build_input_stream`.
+fn build_capture_stream<T: cpal::SizedSample>(
+    device: &Device,
+    stream_config: &cpal::StreamConfig,
+    channels: u16,
+    convert: fn(T) -> f32,
+    role: StreamRole,
+    is_recording: Arc<AtomicBool>,
+    state: Arc<AppState>,
+    tx: crossbeam_channel::Sender<Vec<f32>>,
+    stream_failed: Arc<AtomicBool>,
+) -> cpal::Stream {
+    let err_fn = move |err| {
+        eprintln!("Stream error: {}", err);
+        stream_failed.store(true, Ordering::SeqCst);
+    };
+    device.build_input_stream(
+        stream_config,
+        move |data: &[T], _: &_| {
+            let floats: Vec<f32> = data.iter().map(|&s| convert(s)).collect();
+            let mono = to_mono(&floats, channels);
+            let rms = calculate_rms(&mono);
+            match role {
+                StreamRole::Mic => state.record_input_level(rms),
+                StreamRole::Sys => state.record_output_level(rms),
+            }
+            if is_recording.load(Ordering::SeqCst) {
+                match role {
+                    StreamRole::Mic => state.mic_samples_total.fetch_add(mono.len() as u64, Ordering::Relaxed),
+                    StreamRole::Sys => state.sys_samples_total.fetch_add(mono.len() as u64, Ordering::Relaxed),
+                };
+                tx.send(mono).unwrap();
+            }
+        },
+        err_fn,
+        None,
+    ).unwrap()
+}
+
 fn find_input_device(host: &cpal::Host, config: &Config) -> Option<Device> {
     if let Some(ref name) = config.input_device_name {
         if let Ok(devices) = host.input_devices() {
@@ -20,7 +227,14 @@ fn find_input_device(host: &cpal::Host, config: &Config) -> Option<Device> {
                 }
             }
         }
-        println!("Warning: Configured input device '{}' not found, using default", name);
+        println!("Warning: Configured input device '{}' not found, falling back to index/default", name);
+    }
+    if let Some(index) = config.input_device_index {
+        if let Some(device) = host.input_devices().ok().and_then(|mut d| d.nth(index)) {
+            println!("Using input device at index {}: {}", index, device.name().unwrap_or_default());
+            return Some(device);
+        }
+        println!("Warning: Configured input device index {} not found, using default", index);
     }
     host.default_input_device()
 }
@@ -37,11 +251,121 @@ fn find_output_device(host: &cpal::Host, config: &Config) -> Option<Device> {
                 }
             }
         }
-        println!("Warning: Configured output device '{}' not found, using default", name);
+        println!("Warning: Configured output device '{}' not found, falling back to index/default", name);
+    }
+    if let Some(index) = config.output_device_index {
+        if let Some(device) = host.output_devices().ok().and_then(|mut d| d.nth(index)) {
+            println!("Using output device at index {}: {}", index, device.name().unwrap_or_default());
+            return Some(device);
+        }
+        println!("Warning: Configured output device index {} not found, using default", index);
     }
     host.default_output_device()
 }
 
+/// Resolves the `StreamConfig` actually handed to `build_input_stream`: starts from
+/// the device's default config, then applies the configured fixed buffer size and/or
+/// sample-rate override if the device's `supported_input_configs()` actually offers a
+/// range covering the requested rate. Falls back to the default rate with a warning
+/// otherwise, since forcing an unsupported rate would just fail stream construction.
+fn resolve_input_stream_config(device: &Device, default_config: &cpal::SupportedStreamConfig, config: &Config) -> (cpal::StreamConfig, u32) {
+    let sample_rate = resolve_sample_rate(
+        config.input_sample_rate,
+        default_config,
+        device.supported_input_configs().ok().map(|r| r.collect::<Vec<_>>()),
+        "input",
+    );
+    let mut stream_config: cpal::StreamConfig = default_config.clone().into();
+    stream_config.sample_rate = cpal::SampleRate(sample_rate);
+    if let Some(frames) = config.input_buffer_frames {
+        stream_config.buffer_size = cpal::BufferSize::Fixed(frames);
+    }
+    (stream_config, sample_rate)
+}
+
+/// System-audio counterpart of `resolve_input_stream_config`; see there for rationale.
+fn resolve_output_stream_config(device: &Device, default_config: &cpal::SupportedStreamConfig, config: &Config) -> (cpal::StreamConfig, u32) {
+    let sample_rate = resolve_sample_rate(
+        config.output_sample_rate,
+        default_config,
+        device.supported_output_configs().ok().map(|r| r.collect::<Vec<_>>()),
+        "output",
+    );
+    let mut stream_config: cpal::StreamConfig = default_config.clone().into();
+    stream_config.sample_rate = cpal::SampleRate(sample_rate);
+    if let Some(frames) = config.output_buffer_frames {
+        stream_config.buffer_size = cpal::BufferSize::Fixed(frames);
+    }
+    (stream_config, sample_rate)
+}
+
+fn resolve_sample_rate(
+    override_rate: Option<u32>,
+    default_config: &cpal::SupportedStreamConfig,
+    supported: Option<Vec<cpal::SupportedStreamConfigRange>>,
+    label: &str,
+) -> u32 {
+    let Some(requested) = override_rate else {
+        return default_config.sample_rate().0;
+    };
+    let supports_requested = supported
+        .unwrap_or_default()
+        .iter()
+        .filter(|range| range.channels() == default_config.channels() && range.sample_format() == default_config.sample_format())
+        .any(|range| range.min_sample_rate().0 <= requested && requested <= range.max_sample_rate().0);
+    if supports_requested {
+        requested
+    } else {
+        println!(
+            "Warning: {} device doesn't support requested sample rate {}Hz, using default {}Hz",
+            label, requested, default_config.sample_rate().0
+        );
+        default_config.sample_rate().0
+    }
+}
+
+/// Replays whatever `upload_queue` still has pending from a previous run. Segments
+/// upload with no chunk-timing header (the in-memory clock origin that produced
+/// the original one is long gone) but otherwise go through the normal retrying
+/// `upload_segment` path; once every segment for a recording is confirmed, that
+/// recording is finalized too, since a restarted companion can't resume it anyway.
+fn recover_orphaned_segments(state: &Arc<AppState>) {
+    let pending = upload_queue::reconcile_with_disk();
+    if pending.is_empty() {
+        return;
+    }
+    println!("Found {} orphaned segment(s) from a previous run, re-uploading...", pending.len());
+
+    let config = state.config.lock().unwrap().clone();
+    let mut recording_ids: std::collections::HashSet<i64> = std::collections::HashSet::new();
+    let mut failed_ids: std::collections::HashSet<i64> = std::collections::HashSet::new();
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    rt.block_on(async {
+        for segment in pending {
+            // The original recording_mode isn't journaled, so this hint defaults to
+            // mono; the backend can still fall back to the WAV header's own channel count.
+            match uploader::upload_segment(segment.recording_id, segment.sequence, &segment.file_path, &[], 1, &config).await {
+                Ok(_) => {
+                    if let Err(e) = std::fs::remove_file(&segment.file_path) {
+                        eprintln!("Failed to delete recovered temp file {:?}: {}", segment.file_path, e);
+                    }
+                    upload_queue::remove(&segment.file_path);
+                    recording_ids.insert(segment.recording_id);
+                }
+                Err(e) => {
+                    eprintln!("Failed to re-upload orphaned segment {:?}: {}", segment.file_path, e);
+                    failed_ids.insert(segment.recording_id);
+                }
+            }
+        }
+        for recording_id in recording_ids.difference(&failed_ids).copied() {
+            if let Err(e) = uploader::finalize_recording(recording_id, &config).await {
+                eprintln!("Failed to finalize recovered recording {}: {}", recording_id, e);
+            }
+        }
+    });
+}
+
 pub fn run_audio_loop(state: Arc<AppState>, command_rx: Receiver<AudioCommand>) {
     let host = cpal::default_host();
     
@@ -53,6 +377,10 @@ pub fn run_audio_loop(state: Arc<AppState>, command_rx: Receiver<AudioCommand>)
     println!("  Default Input:  {:?}", input_device);
     println!("  Default Output: {:?}", output_device);
 
+    // Recover any segments orphaned by a crash or an extended backend outage before
+    // accepting new commands, so a recording from a previous run isn't silently lost.
+    recover_orphaned_segments(&state);
+
     // Shared flag to stop the stream thread
     let is_recording = Arc::new(AtomicBool::new(false));
     
@@ -72,12 +400,16 @@ pub fn run_audio_loop(state: Arc<AppState>, command_rx: Receiver<AudioCommand>)
         
         match command {
             AudioCommand::Start(id) => {
+                latch_stream_origin(&state, true);
                 recording_handle = Some(start_segment(id, 1, state.clone(), is_recording.clone()));
             }
             AudioCommand::Resume => {
                 let id = *state.current_recording_id.lock().unwrap();
                 let seq = *state.current_sequence.lock().unwrap();
                 if let Some(rec_id) = id {
+                    // Re-latch so the paused gap shows up as a jump in capture time
+                    // rather than being silently absorbed into the old origin.
+                    latch_stream_origin(&state, false);
                     recording_handle = Some(start_segment(rec_id, seq, state.clone(), is_recording.clone()));
                 }
             }
@@ -144,8 +476,15 @@ fn start_segment(
     let config = state.config.lock().unwrap().clone();
     
     thread::spawn(move || {
+        let mut sequence = sequence;
+        // Retries within the same call when a device drops mid-recording: finalize
+        // and upload the partial segment, re-acquire devices, bump the sequence,
+        // and keep going under the same `is_recording` span. A genuine pause/stop
+        // clears `is_recording` and this loop exits like it always did.
+        loop {
+        let stream_failed = Arc::new(AtomicBool::new(false));
         let host = cpal::default_host();
-        
+
         // 1. Setup Microphone (Input) - use configured or default
         let mic_device = find_input_device(&host, &config).expect("No input device available");
         let mic_config = mic_device.default_input_config().expect("Failed to get mic config");
@@ -157,119 +496,149 @@ fn start_segment(
         let sys_config = sys_device.default_output_config().expect("Failed to get sys config");
         let sys_channels = sys_config.channels();
 
-        println!("Mic: {} ({}ch, {}Hz)", mic_device.name().unwrap_or_default(), mic_channels, mic_config.sample_rate().0);
-        println!("Sys: {} ({}ch, {}Hz)", sys_device.name().unwrap_or_default(), sys_channels, sys_config.sample_rate().0);
+        // Resolve the actual per-device StreamConfig up front so the buffer size and
+        // sample rate (if overridden in Config) are reflected in every downstream use
+        // of "the mic/sys rate" below, not just the stream that's actually opened.
+        let (mic_stream_config, mic_sample_rate) = resolve_input_stream_config(&mic_device, &mic_config, &config);
+        let (sys_stream_config, sys_sample_rate) = resolve_output_stream_config(&sys_device, &sys_config, &config);
 
-        // Target format: Mono, 16-bit, Mic Sample Rate (Master Clock)
-        let spec = hound::WavSpec {
-            channels: 1,
-            sample_rate: mic_config.sample_rate().0,
-            bits_per_sample: 16,
-            sample_format: hound::SampleFormat::Int,
-        };
+        println!("Mic: {} ({}ch, {}Hz)", mic_device.name().unwrap_or_default(), mic_channels, mic_sample_rate);
+        println!("Sys: {} ({}ch, {}Hz)", sys_device.name().unwrap_or_default(), sys_channels, sys_sample_rate);
 
-        let filename = format!("temp_{}_{}.wav", recording_id, sequence);
+        // Snapshot the clock mapping for this segment before any samples are captured,
+        // so `first_sample_index` lines up with the first frame each callback sends.
+        let origin = state.stream_origin.lock().unwrap()
+            .expect("stream origin latched by AudioCommand::Start/Resume before start_segment");
+        let monotonic_nanos = origin.instant.duration_since(state.process_epoch).as_nanos() as u64;
+        let capture_unix_nanos = origin.unix_time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as u64;
+        let chunk_headers = vec![
+            ChunkHeader {
+                stream: "input",
+                first_sample_index: state.mic_samples_total.load(Ordering::SeqCst),
+                capture_unix_nanos,
+                monotonic_nanos,
+                sample_rate: mic_sample_rate,
+            },
+            ChunkHeader {
+                stream: "output",
+                first_sample_index: state.sys_samples_total.load(Ordering::SeqCst),
+                capture_unix_nanos,
+                monotonic_nanos,
+                sample_rate: sys_sample_rate,
+            },
+        ];
+
+        // Dual-track mode always writes WAV (stereo mic/sys pairs); Opus only applies
+        // to the mixed-down single-track path.
+        let extension = match config.recording_mode {
+            RecordingMode::Dual => "wav",
+            RecordingMode::Mixed => match &config.segment_codec {
+                SegmentCodec::Wav => "wav",
+                SegmentCodec::Opus => "opus",
+            },
+        };
+        let filename = format!("temp_{}_{}.{}", recording_id, sequence, extension);
         let path = std::env::current_dir().unwrap().join(&filename);
-        
-        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
-        
+
+        // Channel count carried alongside the upload so the backend knows how to
+        // parse the file without guessing from the WAV header alone.
+        let upload_channels: u16 = match config.recording_mode {
+            RecordingMode::Dual => 2,
+            RecordingMode::Mixed => 1,
+        };
+
+        let mut sink = match config.recording_mode {
+            RecordingMode::Dual => {
+                let spec = hound::WavSpec {
+                    channels: 2,
+                    sample_rate: mic_sample_rate,
+                    bits_per_sample: 16,
+                    sample_format: hound::SampleFormat::Int,
+                };
+                SegmentSink::WavDual(hound::WavWriter::create(&path, spec).unwrap())
+            }
+            RecordingMode::Mixed => match &config.segment_codec {
+                SegmentCodec::Wav => {
+                    // Mono, 16-bit, Mic Sample Rate (Master Clock)
+                    let spec = hound::WavSpec {
+                        channels: 1,
+                        sample_rate: mic_sample_rate,
+                        bits_per_sample: 16,
+                        sample_format: hound::SampleFormat::Int,
+                    };
+                    SegmentSink::Wav(hound::WavWriter::create(&path, spec).unwrap())
+                }
+                SegmentCodec::Opus => {
+                    let file = std::fs::File::create(&path).unwrap();
+                    // Ogg stream serial doesn't need to be globally unique, just unique
+                    // per recording; recording_id/sequence is a convenient source.
+                    let serial = (recording_id as u32).wrapping_mul(31).wrapping_add(sequence as u32);
+                    let writer = OpusSegmentWriter::new(file, serial, OPUS_BITRATE_BPS)
+                        .expect("Failed to initialize Opus encoder");
+                    let resampler = LinearResampler::new(
+                        crate::opus_codec::OPUS_SAMPLE_RATE as f64 / mic_sample_rate as f64,
+                    );
+                    SegmentSink::Opus { writer, resampler }
+                }
+            },
+        };
+
         // Channels for data transfer
         let (mic_tx, mic_rx) = crossbeam_channel::unbounded::<Vec<f32>>();
         let (sys_tx, sys_rx) = crossbeam_channel::unbounded::<Vec<f32>>();
-        
-        let err_fn = |err| eprintln!("Stream error: {}", err);
-        
-        // Helper to convert interleaved to mono
-        let to_mono = |data: &[f32], channels: u16| -> Vec<f32> {
-            if channels == 1 {
-                return data.to_vec();
-            }
-            let mut mono = Vec::with_capacity(data.len() / channels as usize);
-            for chunk in data.chunks(channels as usize) {
-                let sum: f32 = chunk.iter().sum();
-                mono.push(sum / channels as f32);
-            }
-            mono
-        };
-        
-        // Helper to calculate RMS level (0.0 to 1.0)
-        fn calculate_rms(data: &[f32]) -> f32 {
-            if data.is_empty() {
-                return 0.0;
-            }
-            let sum_squares: f32 = data.iter().map(|s| s * s).sum();
-            (sum_squares / data.len() as f32).sqrt()
-        }
 
         // 3. Build Mic Stream
-        let is_recording_mic = is_recording.clone();
-        let state_mic = state.clone();
-        let mic_stream = mic_device.build_input_stream(
-            &mic_config.into(),
-            move |data: &[f32], _: &_| {
-                let mono = to_mono(data, mic_channels);
-                // Update input level (always, for monitoring)
-                let rms = calculate_rms(&mono);
-                state_mic.record_input_level(rms);
-                
-                if is_recording_mic.load(Ordering::SeqCst) {
-                    mic_tx.send(mono).unwrap();
-                }
-            },
-            err_fn,
-            None
-        ).unwrap();
+        let mic_stream = match mic_config.sample_format() {
+            cpal::SampleFormat::F32 => build_capture_stream::<f32>(&mic_device, &mic_stream_config, mic_channels, f32_identity, StreamRole::Mic, is_recording.clone(), state.clone(), mic_tx, stream_failed.clone()),
+            cpal::SampleFormat::I16 => build_capture_stream::<i16>(&mic_device, &mic_stream_config, mic_channels, i16_to_f32, StreamRole::Mic, is_recording.clone(), state.clone(), mic_tx, stream_failed.clone()),
+            cpal::SampleFormat::U16 => build_capture_stream::<u16>(&mic_device, &mic_stream_config, mic_channels, u16_to_f32, StreamRole::Mic, is_recording.clone(), state.clone(), mic_tx, stream_failed.clone()),
+            other => panic!("Unsupported mic sample format: {:?}", other),
+        };
 
         // 4. Build System Stream
-        let is_recording_sys = is_recording.clone();
-        let state_sys = state.clone();
-        let sys_stream = sys_device.build_input_stream(
-            &sys_config.into(),
-            move |data: &[f32], _: &_| {
-                let mono = to_mono(data, sys_channels);
-                // Update output level (always, for monitoring)
-                let rms = calculate_rms(&mono);
-                state_sys.record_output_level(rms);
-                
-                if is_recording_sys.load(Ordering::SeqCst) {
-                    sys_tx.send(mono).unwrap();
-                }
-            },
-            err_fn,
-            None
-        ).unwrap();
-        
+        let sys_stream = match sys_config.sample_format() {
+            cpal::SampleFormat::F32 => build_capture_stream::<f32>(&sys_device, &sys_stream_config, sys_channels, f32_identity, StreamRole::Sys, is_recording.clone(), state.clone(), sys_tx, stream_failed.clone()),
+            cpal::SampleFormat::I16 => build_capture_stream::<i16>(&sys_device, &sys_stream_config, sys_channels, i16_to_f32, StreamRole::Sys, is_recording.clone(), state.clone(), sys_tx, stream_failed.clone()),
+            cpal::SampleFormat::U16 => build_capture_stream::<u16>(&sys_device, &sys_stream_config, sys_channels, u16_to_f32, StreamRole::Sys, is_recording.clone(), state.clone(), sys_tx, stream_failed.clone()),
+            other => panic!("Unsupported system audio sample format: {:?}", other),
+        };
+
         mic_stream.play().unwrap();
         sys_stream.play().unwrap();
-        
+
         // 5. Mixing Loop
-        // We use Mic as the master clock.
+        // We use Mic as the master clock. System audio is resampled onto that clock
+        // below since the two devices rarely share a sample rate.
         let mut sys_buffer: Vec<f32> = Vec::new();
-        
-        while is_recording.load(Ordering::SeqCst) {
+        let mut sys_resampler = LinearResampler::new(mic_sample_rate as f64 / sys_sample_rate as f64);
+
+        while is_recording.load(Ordering::SeqCst) && !stream_failed.load(Ordering::SeqCst) {
             // Block on Mic data (Master)
             if let Ok(mic_data) = mic_rx.recv_timeout(std::time::Duration::from_millis(500)) {
-                // Collect available System data
+                // Collect available System data, resampled onto the mic's clock
                 while let Ok(sys_chunk) = sys_rx.try_recv() {
-                    sys_buffer.extend(sys_chunk);
+                    sys_resampler.process(&sys_chunk, &mut sys_buffer);
                 }
                 
-                // Mix
-                for (i, mic_sample) in mic_data.iter().enumerate() {
-                    let mut mixed = *mic_sample;
-                    
-                    // If we have system audio, add it
-                    if i < sys_buffer.len() {
-                        mixed += sys_buffer[i];
+                // Pad the resampled system audio out to the mic chunk's length with
+                // silence so both the mixed and dual-track paths below can assume
+                // matching lengths.
+                let mut sys_slice = vec![0.0f32; mic_data.len()];
+                let take = sys_buffer.len().min(mic_data.len());
+                sys_slice[..take].copy_from_slice(&sys_buffer[..take]);
+
+                match config.recording_mode {
+                    RecordingMode::Mixed => {
+                        let mixed_samples: Vec<f32> = mic_data.iter().zip(sys_slice.iter())
+                            .map(|(mic_sample, sys_sample)| (mic_sample + sys_sample).clamp(-1.0, 1.0))
+                            .collect();
+                        sink.write_mixed(&mixed_samples);
+                    }
+                    RecordingMode::Dual => {
+                        sink.write_dual(&mic_data, &sys_slice);
                     }
-                    
-                    // Hard clip to prevent wrapping
-                    mixed = mixed.clamp(-1.0, 1.0);
-                    
-                    let amplitude = i16::MAX as f32;
-                    writer.write_sample((mixed * amplitude) as i16).unwrap();
                 }
-                
+
                 // Remove used system samples
                 if sys_buffer.len() >= mic_data.len() {
                     sys_buffer.drain(0..mic_data.len());
@@ -282,25 +651,60 @@ fn start_segment(
         // Flush remaining Mic data? 
         // Usually we stop immediately on pause/stop.
         
-        writer.finalize().unwrap();
+        sink.finalize();
         drop(mic_stream);
         drop(sys_stream);
         
         println!("Segment finished: {:?}", path);
         
-        // Upload
+        // Upload. Journaled before the attempt so a crash mid-upload still leaves
+        // a record that points back at the file for the next startup to pick up.
+        upload_queue::enqueue(upload_queue::QueuedSegment {
+            file_path: path.clone(),
+            recording_id,
+            sequence,
+        });
         let rt = tokio::runtime::Runtime::new().unwrap();
         rt.block_on(async {
-            match uploader::upload_segment(recording_id, sequence, &path, &config).await {
+            match uploader::upload_segment(recording_id, sequence, &path, &chunk_headers, upload_channels, &config).await {
                 Ok(_) => {
                     println!("Segment uploaded successfully");
+                    state.upload_successes_total.fetch_add(1, Ordering::Relaxed);
                     // Only delete file if upload was successful
                     if let Err(e) = std::fs::remove_file(&path) {
                         eprintln!("Failed to delete temp file {:?}: {}", path, e);
                     }
+                    upload_queue::remove(&path);
+                },
+                Err(e) => {
+                    state.upload_failures_total.fetch_add(1, Ordering::Relaxed);
+                    eprintln!("Failed to upload segment: {}. File preserved at {:?}", e, path);
                 },
-                Err(e) => eprintln!("Failed to upload segment: {}. File preserved at {:?}", e, path),
             }
         });
+
+        // Only auto-recover from a device drop; a real pause/stop already cleared
+        // `is_recording` and should fall through and let the thread end normally.
+        if stream_failed.load(Ordering::SeqCst) && is_recording.load(Ordering::SeqCst) {
+            notifications::show_notification("Nojoin", "Audio device disconnected. Reconnecting...");
+            *state.status.lock().unwrap() = AppStatus::DeviceError;
+
+            sequence += 1;
+            *state.current_sequence.lock().unwrap() = sequence;
+
+            // Give a genuinely-unplugged device a moment to reappear before retrying.
+            thread::sleep(std::time::Duration::from_millis(500));
+
+            {
+                let mut status = state.status.lock().unwrap();
+                if *status == AppStatus::DeviceError {
+                    *status = AppStatus::Recording;
+                }
+            }
+            continue;
+        }
+
+        break;
+        }
     })
 }
@@ -1,10 +1,160 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::time::SystemTime;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Config {
     pub api_url: String,
     pub api_token: String,
+    /// OAuth access token for the backend API. Takes priority over the legacy
+    /// `api_token` when present; refreshed transparently by `AppState::valid_token`.
+    #[serde(default)]
+    pub access_token: Option<String>,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    #[serde(default)]
+    pub expires_at: Option<SystemTime>,
+    /// Per-install secret required (via `Authorization: Bearer` or `?token=`) to call
+    /// the local control server. Generated once on first run and persisted from then on.
+    #[serde(default = "generate_control_secret")]
+    pub control_secret: String,
+    /// How long a scoped token minted by `/auth/scoped` stays valid.
+    #[serde(default = "default_scoped_token_ttl_secs")]
+    pub scoped_token_ttl_secs: u64,
+    /// Stable per-install identifier, generated once and persisted. Hashed into
+    /// a 0-99 bucket so `updater::UpdateChecker` can gate phased rollouts
+    /// (`Manifest::rollout`) without the server needing to track individual
+    /// installs.
+    #[serde(default = "generate_install_id")]
+    pub install_id: String,
+    /// Endpoint returning an `updater::Manifest` JSON document. Defaults to a
+    /// manifest file checked into the release repo itself, so shipping a new
+    /// rollout doesn't require standing up a separate service.
+    #[serde(default = "default_update_manifest_url")]
+    pub update_manifest_url: String,
+    /// Release channel to check updates against. `Beta` opts into
+    /// `updater::Manifest::prereleases`; `Stable` only ever installs
+    /// `Manifest::version`, and skips it if that build is itself tagged as a
+    /// pre-release.
+    #[serde(default)]
+    pub channel: ReleaseChannel,
+    /// Minimum time between background update checks that actually hit the
+    /// network; see `updater::check_for_updates`. A manual "Check for Updates"
+    /// from the tray menu always bypasses this.
+    #[serde(default = "default_check_interval_hours")]
+    pub check_interval_hours: u64,
+    /// Timeout for the update manifest HTTP request.
+    #[serde(default = "default_update_check_timeout_secs")]
+    pub update_check_timeout_secs: u64,
+    /// Optional rustls cert/key paths. When both are set, the control server serves
+    /// HTTPS instead of plain HTTP.
+    #[serde(default)]
+    pub tls_cert_path: Option<String>,
+    #[serde(default)]
+    pub tls_key_path: Option<String>,
+    #[serde(default)]
+    pub segment_codec: SegmentCodec,
+    /// Preferred capture devices by exact name, matched in `find_input_device`/
+    /// `find_output_device`. Falls back to `*_device_index` and then the OS default
+    /// when unset or when the named device isn't currently attached.
+    #[serde(default)]
+    pub input_device_name: Option<String>,
+    #[serde(default)]
+    pub output_device_name: Option<String>,
+    /// Index into `Host::input_devices()`/`output_devices()`, tried when name
+    /// matching is unset or fails. Devices aren't guaranteed to enumerate in a
+    /// stable order across OS driver updates, so name matching stays the primary
+    /// selector and this is strictly a fallback.
+    #[serde(default)]
+    pub input_device_index: Option<usize>,
+    #[serde(default)]
+    pub output_device_index: Option<usize>,
+    /// Fixed capture buffer size in frames. Some backends default to a large
+    /// buffer that adds noticeable latency to level metering; set this to trade
+    /// CPU wakeups for lower latency. Left unset, cpal picks its own default.
+    #[serde(default)]
+    pub input_buffer_frames: Option<u32>,
+    #[serde(default)]
+    pub output_buffer_frames: Option<u32>,
+    /// Explicit capture sample rate, validated against the device's supported
+    /// configs at stream setup and ignored with a warning if unsupported. Useful
+    /// for forcing the mic and loopback device onto a matching rate.
+    #[serde(default)]
+    pub input_sample_rate: Option<u32>,
+    #[serde(default)]
+    pub output_sample_rate: Option<u32>,
+    /// Whether segments are written as a single mixed-down mono track or as a
+    /// 2-channel (mic, system audio) track pair. Dual-track lets the backend
+    /// diarize mic vs. system audio instead of trying to separate a pre-mixed signal.
+    #[serde(default)]
+    pub recording_mode: RecordingMode,
+}
+
+/// See `Config::recording_mode`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordingMode {
+    Mixed,
+    Dual,
+}
+
+impl Default for RecordingMode {
+    fn default() -> Self {
+        RecordingMode::Mixed
+    }
+}
+
+/// See `Config::channel`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReleaseChannel {
+    Stable,
+    Beta,
+}
+
+impl Default for ReleaseChannel {
+    fn default() -> Self {
+        ReleaseChannel::Stable
+    }
+}
+
+/// Segment file format uploaded to the backend. `Opus` trades a little CPU for a
+/// large bandwidth reduction over raw 16-bit WAV segments.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SegmentCodec {
+    Wav,
+    Opus,
+}
+
+impl Default for SegmentCodec {
+    fn default() -> Self {
+        SegmentCodec::Wav
+    }
+}
+
+fn generate_control_secret() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+fn default_scoped_token_ttl_secs() -> u64 {
+    3600
+}
+
+fn generate_install_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+fn default_update_manifest_url() -> String {
+    "https://raw.githubusercontent.com/Valtora/Nojoin/main/update-manifest.json".to_string()
+}
+
+fn default_check_interval_hours() -> u64 {
+    24
+}
+
+fn default_update_check_timeout_secs() -> u64 {
+    10
 }
 
 impl Config {
@@ -33,6 +183,28 @@ impl Config {
             let default_config = Config {
                 api_url: "http://localhost:8000/api/v1".to_string(),
                 api_token: "".to_string(),
+                access_token: None,
+                refresh_token: None,
+                expires_at: None,
+                control_secret: generate_control_secret(),
+                scoped_token_ttl_secs: default_scoped_token_ttl_secs(),
+                install_id: generate_install_id(),
+                update_manifest_url: default_update_manifest_url(),
+                channel: ReleaseChannel::default(),
+                check_interval_hours: default_check_interval_hours(),
+                update_check_timeout_secs: default_update_check_timeout_secs(),
+                tls_cert_path: None,
+                tls_key_path: None,
+                segment_codec: SegmentCodec::default(),
+                input_device_name: None,
+                output_device_name: None,
+                input_device_index: None,
+                output_device_index: None,
+                input_buffer_frames: None,
+                output_buffer_frames: None,
+                input_sample_rate: None,
+                output_sample_rate: None,
+                recording_mode: RecordingMode::default(),
             };
             
             // Try to write to current directory
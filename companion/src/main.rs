@@ -1,7 +1,7 @@
 #![windows_subsystem = "windows"]
 
 use std::sync::{Arc, Mutex};
-use std::sync::atomic::{AtomicU32, AtomicBool, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicBool, Ordering};
 use std::thread;
 use std::time::{Duration, Instant};
 use std::path::PathBuf;
@@ -18,6 +18,9 @@ mod uploader;
 mod config;
 mod notifications;
 mod updater;
+mod metrics;
+mod opus_codec;
+mod upload_queue;
 
 use state::{AppState, AppStatus};
 use config::Config;
@@ -117,6 +120,17 @@ fn main() {
         accumulated_duration: Mutex::new(Duration::new(0, 0)),
         input_level: AtomicU32::new(0),
         output_level: AtomicU32::new(0),
+        recordings_started_total: AtomicU64::new(0),
+        recordings_stopped_total: AtomicU64::new(0),
+        upload_successes_total: AtomicU64::new(0),
+        upload_failures_total: AtomicU64::new(0),
+        refresh_lock: tokio::sync::Mutex::new(()),
+        scoped_tokens: Mutex::new(std::collections::HashMap::new()),
+        ws_tx: tokio::sync::broadcast::channel(64).0,
+        process_epoch: Instant::now(),
+        stream_origin: Mutex::new(None),
+        mic_samples_total: AtomicU64::new(0),
+        sys_samples_total: AtomicU64::new(0),
         web_url: Mutex::new(None),
         is_backend_connected: AtomicBool::new(false),
     });
@@ -243,16 +257,17 @@ fn main() {
             }
         });
 
-        // Auto-update check (runs once at startup, then daily)
+        // Auto-update check. Wakes hourly, but `check_for_updates` itself skips
+        // the network call unless `Config::check_interval_hours` has elapsed
+        // since the last one, so this stays cheap regardless of how often it wakes.
+        let state_update = state_server.clone();
         rt.spawn(async move {
             // Initial delay to let the app settle
             tokio::time::sleep(Duration::from_secs(5)).await;
-            updater::check_for_updates().await;
-            
-            // Check daily
             loop {
-                tokio::time::sleep(Duration::from_secs(24 * 60 * 60)).await;
-                updater::check_for_updates().await;
+                let config = state_update.config.lock().unwrap().clone();
+                updater::check_for_updates(&config).await;
+                tokio::time::sleep(Duration::from_secs(60 * 60)).await;
             }
         });
 
@@ -273,6 +288,7 @@ fn main() {
                  AppStatus::Paused => "Status: Recording Paused",
                  AppStatus::Uploading => "Status: Uploading Recording",
                  AppStatus::BackendOffline => "Status: Backend Not Found...",
+                 AppStatus::DeviceError => "Status: Device Disconnected, Reconnecting...",
                  AppStatus::Error(_) => "Status: Error",
              };
              let _ = status_i.set_text(status_text);
@@ -304,9 +320,10 @@ fn main() {
                  }
             } else if event.id == check_updates_i.id() {
                  // Trigger manual update check
-                 std::thread::spawn(|| {
+                 let config = state.config.lock().unwrap().clone();
+                 std::thread::spawn(move || {
                      let rt = tokio::runtime::Runtime::new().unwrap();
-                     rt.block_on(updater::check_for_updates_interactive());
+                     rt.block_on(updater::check_for_updates_interactive(&config));
                  });
             } else if event.id == view_logs_i.id() {
                  let log_path = get_log_path();
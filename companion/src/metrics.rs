@@ -0,0 +1,78 @@
+use crate::state::{AppState, AppStatus};
+use std::sync::atomic::Ordering;
+
+/// Render the current `AppState` as Prometheus text exposition format.
+pub fn render(state: &AppState) -> String {
+    let mut out = String::new();
+
+    let status = state.status.lock().unwrap().clone();
+    let duration = {
+        let acc = *state.accumulated_duration.lock().unwrap();
+        let start = *state.recording_start_time.lock().unwrap();
+        match status {
+            AppStatus::Recording => start
+                .and_then(|s| s.elapsed().ok())
+                .map(|e| acc + e)
+                .unwrap_or(acc),
+            _ => acc,
+        }
+    };
+
+    out.push_str("# HELP nojoin_status Current companion status (1 for the active variant, 0 otherwise)\n");
+    out.push_str("# TYPE nojoin_status gauge\n");
+    for variant in ["idle", "recording", "paused", "uploading", "backend_offline", "device_error", "error"] {
+        let value = match (&status, variant) {
+            (AppStatus::Idle, "idle") => 1,
+            (AppStatus::Recording, "recording") => 1,
+            (AppStatus::Paused, "paused") => 1,
+            (AppStatus::Uploading, "uploading") => 1,
+            (AppStatus::BackendOffline, "backend_offline") => 1,
+            (AppStatus::DeviceError, "device_error") => 1,
+            (AppStatus::Error(_), "error") => 1,
+            _ => 0,
+        };
+        out.push_str(&format!("nojoin_status{{state=\"{}\"}} {}\n", variant, value));
+    }
+
+    out.push_str("# HELP nojoin_recording_duration_seconds Duration of the in-progress recording\n");
+    out.push_str("# TYPE nojoin_recording_duration_seconds gauge\n");
+    out.push_str(&format!("nojoin_recording_duration_seconds {}\n", duration.as_secs()));
+
+    out.push_str("# HELP nojoin_input_level Current mic input level (0-100)\n");
+    out.push_str("# TYPE nojoin_input_level gauge\n");
+    out.push_str(&format!("nojoin_input_level {}\n", state.input_level.load(Ordering::Relaxed)));
+
+    out.push_str("# HELP nojoin_output_level Current system output level (0-100)\n");
+    out.push_str("# TYPE nojoin_output_level gauge\n");
+    out.push_str(&format!("nojoin_output_level {}\n", state.output_level.load(Ordering::Relaxed)));
+
+    out.push_str("# HELP nojoin_recordings_started_total Recordings started\n");
+    out.push_str("# TYPE nojoin_recordings_started_total counter\n");
+    out.push_str(&format!(
+        "nojoin_recordings_started_total {}\n",
+        state.recordings_started_total.load(Ordering::Relaxed)
+    ));
+
+    out.push_str("# HELP nojoin_recordings_stopped_total Recordings stopped\n");
+    out.push_str("# TYPE nojoin_recordings_stopped_total counter\n");
+    out.push_str(&format!(
+        "nojoin_recordings_stopped_total {}\n",
+        state.recordings_stopped_total.load(Ordering::Relaxed)
+    ));
+
+    out.push_str("# HELP nojoin_upload_successes_total Segment uploads that succeeded\n");
+    out.push_str("# TYPE nojoin_upload_successes_total counter\n");
+    out.push_str(&format!(
+        "nojoin_upload_successes_total {}\n",
+        state.upload_successes_total.load(Ordering::Relaxed)
+    ));
+
+    out.push_str("# HELP nojoin_upload_failures_total Segment uploads that failed after exhausting retries\n");
+    out.push_str("# TYPE nojoin_upload_failures_total counter\n");
+    out.push_str(&format!(
+        "nojoin_upload_failures_total {}\n",
+        state.upload_failures_total.load(Ordering::Relaxed)
+    ));
+
+    out
+}
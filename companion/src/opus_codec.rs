@@ -0,0 +1,106 @@
+use audiopus::coder::Encoder;
+use audiopus::{Application, Bitrate, Channels, SampleRate};
+use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+use std::io::Write;
+
+/// Opus only supports 8/12/16/24/48 kHz; segments are always resampled onto this
+/// rate before encoding (see `LinearResampler` in `audio.rs`).
+pub const OPUS_SAMPLE_RATE: u32 = 48_000;
+/// 20ms frames at 48 kHz mono, the frame size cpal/Opus deployments settle on.
+pub const FRAME_SAMPLES: usize = 960;
+
+/// Encodes mono f32 audio to Opus and muxes it into an Ogg container, one segment
+/// file per call site (mirrors how `hound::WavWriter` is used for the "wav" codec).
+/// Samples are buffered until a full 20ms frame is available; `finalize` zero-pads
+/// and flushes whatever partial frame is left so segment length stays accurate via
+/// the tracked (unpadded) granule position.
+pub struct OpusSegmentWriter<W: Write> {
+    encoder: Encoder,
+    packet_writer: PacketWriter<W>,
+    serial: u32,
+    granule_pos: u64,
+    pending: Vec<f32>,
+}
+
+impl<W: Write> OpusSegmentWriter<W> {
+    pub fn new(sink: W, serial: u32, bitrate_bps: i32) -> anyhow::Result<Self> {
+        let mut encoder = Encoder::new(SampleRate::Hz48000, Channels::Mono, Application::Voip)
+            .map_err(|e| anyhow::anyhow!("Failed to create Opus encoder: {}", e))?;
+        encoder
+            .set_bitrate(Bitrate::BitsPerSecond(bitrate_bps))
+            .map_err(|e| anyhow::anyhow!("Failed to set Opus bitrate: {}", e))?;
+
+        let mut packet_writer = PacketWriter::new(sink);
+        write_opus_headers(&mut packet_writer, serial)?;
+
+        Ok(Self {
+            encoder,
+            packet_writer,
+            serial,
+            granule_pos: 0,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Buffers `samples` and flushes every complete 20ms frame as an Opus packet.
+    pub fn push(&mut self, samples: &[f32]) -> anyhow::Result<()> {
+        self.pending.extend_from_slice(samples);
+        while self.pending.len() >= FRAME_SAMPLES {
+            let frame: Vec<f32> = self.pending.drain(0..FRAME_SAMPLES).collect();
+            self.encode_and_write(&frame, FRAME_SAMPLES as u64, PacketWriteEndInfo::NormalPacket)?;
+        }
+        Ok(())
+    }
+
+    /// Zero-pads and encodes whatever partial frame remains, then closes the stream.
+    /// Always writes a final `EndStream` page, even when `pending` is empty (an
+    /// exact multiple of `FRAME_SAMPLES`, or no samples at all) - otherwise the last
+    /// page written is a `NormalPacket` and the Ogg stream never gets an
+    /// end-of-stream marker.
+    pub fn finalize(mut self) -> anyhow::Result<()> {
+        let true_len = self.pending.len() as u64;
+        let mut frame = std::mem::take(&mut self.pending);
+        frame.resize(FRAME_SAMPLES, 0.0);
+        self.encode_and_write(&frame, true_len, PacketWriteEndInfo::EndStream)?;
+        Ok(())
+    }
+
+    fn encode_and_write(&mut self, frame: &[f32], sample_count: u64, end_info: PacketWriteEndInfo) -> anyhow::Result<()> {
+        let mut buf = [0u8; 4000];
+        let len = self
+            .encoder
+            .encode_float(frame, &mut buf)
+            .map_err(|e| anyhow::anyhow!("Opus encode failed: {}", e))?;
+        self.granule_pos += sample_count;
+        self.packet_writer
+            .write_packet(buf[..len].to_vec(), self.serial, end_info, self.granule_pos)
+            .map_err(|e| anyhow::anyhow!("Failed to write Ogg packet: {}", e))?;
+        Ok(())
+    }
+}
+
+fn write_opus_headers<W: Write>(packet_writer: &mut PacketWriter<W>, serial: u32) -> anyhow::Result<()> {
+    let mut head = Vec::with_capacity(19);
+    head.extend_from_slice(b"OpusHead");
+    head.push(1); // version
+    head.push(1); // channel count (mono)
+    head.extend_from_slice(&0u16.to_le_bytes()); // pre-skip
+    head.extend_from_slice(&OPUS_SAMPLE_RATE.to_le_bytes()); // original input sample rate
+    head.extend_from_slice(&0i16.to_le_bytes()); // output gain
+    head.push(0); // channel mapping family (single stream)
+    packet_writer
+        .write_packet(head, serial, PacketWriteEndInfo::EndPage, 0)
+        .map_err(|e| anyhow::anyhow!("Failed to write OpusHead: {}", e))?;
+
+    let vendor = b"nojoin-companion";
+    let mut tags = Vec::with_capacity(8 + 4 + vendor.len() + 4);
+    tags.extend_from_slice(b"OpusTags");
+    tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    tags.extend_from_slice(vendor);
+    tags.extend_from_slice(&0u32.to_le_bytes()); // no user comments
+    packet_writer
+        .write_packet(tags, serial, PacketWriteEndInfo::EndPage, 0)
+        .map_err(|e| anyhow::anyhow!("Failed to write OpusTags: {}", e))?;
+
+    Ok(())
+}
@@ -1,18 +1,32 @@
 use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    extract::{Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::{self, Next},
     routing::{get, post},
-    Router, Json, extract::State, http::StatusCode,
+    Router, Json, response::{IntoResponse, Response},
 };
 use std::sync::Arc;
-use tower_http::cors::CorsLayer;
+use std::time::{Duration, Instant};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use axum_server::tls_rustls::RustlsConfig;
 use cpal::traits::{DeviceTrait, HostTrait};
+use uuid::Uuid;
 use crate::state::{AppState, AppStatus, AudioCommand};
 use crate::notifications;
 use crate::config::Config;
 use crate::uploader;
+use crate::metrics;
+
+/// How often to sweep `scoped_tokens` for entries past their expiry.
+const SCOPED_TOKEN_SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+/// How often `/ws` subscribers get a fresh level snapshot while recording.
+const LEVELS_BROADCAST_INTERVAL: Duration = Duration::from_millis(50);
 
 pub async fn start_server(state: Arc<AppState>) {
     let app = Router::new()
         .route("/status", get(get_status))
+        .route("/metrics", get(get_metrics))
         .route("/config", get(get_config).post(update_config))
         .route("/devices", get(get_devices))
         .route("/levels", get(get_audio_levels))
@@ -20,29 +34,155 @@ pub async fn start_server(state: Arc<AppState>) {
         .route("/stop", post(stop_recording))
         .route("/pause", post(pause_recording))
         .route("/resume", post(resume_recording))
-        .layer(CorsLayer::permissive())
-        .with_state(state);
+        .route("/auth/scoped", post(issue_scoped_token))
+        .route("/ws", get(ws_upgrade))
+        .layer(middleware::from_fn_with_state(state.clone(), require_control_token))
+        .layer(build_cors_layer(state.clone()))
+        .with_state(state.clone());
+
+    tokio::spawn(scoped_token_sweep_loop(state.clone()));
+    tokio::spawn(broadcast_levels_loop(state.clone()));
+
+    let (cert_path, key_path) = {
+        let config = state.config.lock().unwrap();
+        (config.tls_cert_path.clone(), config.tls_key_path.clone())
+    };
+
+    if let (Some(cert_path), Some(key_path)) = (cert_path, key_path) {
+        match RustlsConfig::from_pem_file(&cert_path, &key_path).await {
+            Ok(tls_config) => {
+                println!("Server running on https://127.0.0.1:12345");
+                let addr: std::net::SocketAddr = "127.0.0.1:12345".parse().unwrap();
+                axum_server::bind_rustls(addr, tls_config)
+                    .serve(app.into_make_service())
+                    .await
+                    .unwrap();
+                return;
+            }
+            Err(e) => {
+                eprintln!("Failed to load TLS cert/key ({}), falling back to plain HTTP", e);
+            }
+        }
+    }
 
     println!("Server running on http://127.0.0.1:12345");
     let listener = tokio::net::TcpListener::bind("127.0.0.1:12345").await.unwrap();
     axum::serve(listener, app).await.unwrap();
 }
 
+/// Requires the per-install `control_secret` (full access) or a live token minted by
+/// `/auth/scoped`, via `Authorization: Bearer <token>` or a `?token=` query param.
+async fn require_control_token(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let token = bearer_token(req.headers()).or_else(|| query_token(req.uri().query()));
+
+    let authorized = match token {
+        Some(token) => {
+            let control_secret = state.config.lock().unwrap().control_secret.clone();
+            if token == control_secret {
+                true
+            } else {
+                let scoped_tokens = state.scoped_tokens.lock().unwrap();
+                matches!(scoped_tokens.get(&token), Some(expires_at) if *expires_at > Instant::now())
+            }
+        }
+        None => false,
+    };
+
+    if authorized {
+        next.run(req).await
+    } else {
+        StatusCode::UNAUTHORIZED.into_response()
+    }
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(|s| s.to_string())
+}
+
+fn query_token(query: Option<&str>) -> Option<String> {
+    query?.split('&').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        (key == "token").then(|| value.to_string())
+    })
+}
+
+/// Allow only the dynamically-discovered `web_url` (set once the backend reports it) as
+/// a CORS origin, instead of the previous `CorsLayer::permissive()`.
+fn build_cors_layer(state: Arc<AppState>) -> CorsLayer {
+    CorsLayer::new()
+        .allow_methods(Any)
+        .allow_headers(Any)
+        .allow_origin(AllowOrigin::predicate(move |origin, _| {
+            let web_url = state.web_url.lock().unwrap().clone();
+            match (origin.to_str().ok(), web_url) {
+                (Some(origin), Some(web_url)) => origin == web_url,
+                _ => false,
+            }
+        }))
+}
+
+#[derive(serde::Serialize)]
+struct ScopedTokenResponse {
+    token: String,
+    expires_in: u64,
+}
+
+async fn issue_scoped_token(State(state): State<Arc<AppState>>) -> ApiResponse<ScopedTokenResponse> {
+    let ttl_secs = state.config.lock().unwrap().scoped_token_ttl_secs;
+    let token = Uuid::new_v4().to_string();
+    let expires_at = Instant::now() + Duration::from_secs(ttl_secs);
+    state.scoped_tokens.lock().unwrap().insert(token.clone(), expires_at);
+    ApiResponse::Success(ScopedTokenResponse { token, expires_in: ttl_secs })
+}
+
+async fn scoped_token_sweep_loop(state: Arc<AppState>) {
+    loop {
+        tokio::time::sleep(SCOPED_TOKEN_SWEEP_INTERVAL).await;
+        let now = Instant::now();
+        state.scoped_tokens.lock().unwrap().retain(|_, expires_at| *expires_at > now);
+    }
+}
+
 use std::time::SystemTime;
 
+/// Uniform response envelope so clients can tell a recoverable backend error
+/// (`Failure`) apart from a fatal local failure like a dead audio channel (`Fatal`).
+#[derive(serde::Serialize)]
+#[serde(tag = "type", content = "content")]
+enum ApiResponse<T> {
+    Success(T),
+    Failure(String),
+    Fatal(String),
+}
+
+impl<T: serde::Serialize> IntoResponse for ApiResponse<T> {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
+
 #[derive(serde::Serialize)]
 struct StatusResponse {
     status: AppStatus,
     duration_seconds: u64,
 }
 
-async fn get_status(State(state): State<Arc<AppState>>) -> Json<StatusResponse> {
+fn build_status_response(state: &AppState) -> StatusResponse {
     let status = state.status.lock().unwrap().clone();
-    
+
     let duration = {
         let acc = *state.accumulated_duration.lock().unwrap();
         let start = *state.recording_start_time.lock().unwrap();
-        
+
         match status {
             AppStatus::Recording => {
                 if let Some(s) = start {
@@ -59,10 +199,18 @@ async fn get_status(State(state): State<Arc<AppState>>) -> Json<StatusResponse>
         }
     };
 
-    Json(StatusResponse {
+    StatusResponse {
         status,
         duration_seconds: duration.as_secs(),
-    })
+    }
+}
+
+async fn get_status(State(state): State<Arc<AppState>>) -> ApiResponse<StatusResponse> {
+    ApiResponse::Success(build_status_response(&state))
+}
+
+async fn get_metrics(State(state): State<Arc<AppState>>) -> String {
+    metrics::render(&state)
 }
 
 #[derive(serde::Serialize)]
@@ -72,17 +220,79 @@ struct AudioLevelsResponse {
     is_recording: bool,
 }
 
-async fn get_audio_levels(State(state): State<Arc<AppState>>) -> Json<AudioLevelsResponse> {
+async fn get_audio_levels(State(state): State<Arc<AppState>>) -> ApiResponse<AudioLevelsResponse> {
     let status = state.status.lock().unwrap().clone();
     let is_recording = matches!(status, AppStatus::Recording);
-    
-    Json(AudioLevelsResponse {
+
+    ApiResponse::Success(AudioLevelsResponse {
         input_level: state.take_input_level(),
         output_level: state.take_output_level(),
         is_recording,
     })
 }
 
+/// A `/ws` frame: the same payload the matching REST endpoint returns, plus a
+/// `kind` discriminator so the client can dispatch without guessing the shape.
+#[derive(serde::Serialize)]
+#[serde(tag = "kind")]
+enum WsFrame {
+    #[serde(rename = "status")]
+    Status(StatusResponse),
+    #[serde(rename = "levels")]
+    Levels(AudioLevelsResponse),
+}
+
+/// Broadcast the current status to `/ws` subscribers. Called after every state
+/// transition; a no-op if nobody is currently connected.
+fn publish_status(state: &AppState) {
+    let frame = WsFrame::Status(build_status_response(state));
+    if let Ok(json) = serde_json::to_string(&frame) {
+        let _ = state.ws_tx.send(json);
+    }
+}
+
+/// ~20Hz level snapshots while recording, so `/ws` subscribers get live meters
+/// without polling `/levels` on a timer.
+async fn broadcast_levels_loop(state: Arc<AppState>) {
+    loop {
+        tokio::time::sleep(LEVELS_BROADCAST_INTERVAL).await;
+
+        let is_recording = matches!(*state.status.lock().unwrap(), AppStatus::Recording);
+        if !is_recording {
+            continue;
+        }
+
+        let frame = WsFrame::Levels(AudioLevelsResponse {
+            input_level: state.get_input_level(),
+            output_level: state.get_output_level(),
+            is_recording,
+        });
+        if let Ok(json) = serde_json::to_string(&frame) {
+            let _ = state.ws_tx.send(json);
+        }
+    }
+}
+
+async fn ws_upgrade(ws: WebSocketUpgrade, State(state): State<Arc<AppState>>) -> Response {
+    ws.on_upgrade(move |socket| handle_ws(socket, state))
+}
+
+async fn handle_ws(mut socket: WebSocket, state: Arc<AppState>) {
+    let initial = WsFrame::Status(build_status_response(&state));
+    if let Ok(json) = serde_json::to_string(&initial) {
+        if socket.send(Message::Text(json)).await.is_err() {
+            return;
+        }
+    }
+
+    let mut rx = state.ws_tx.subscribe();
+    while let Ok(frame) = rx.recv().await {
+        if socket.send(Message::Text(frame)).await.is_err() {
+            break;
+        }
+    }
+}
+
 #[derive(serde::Deserialize)]
 struct StartRequest {
     name: String,
@@ -98,7 +308,7 @@ struct StartResponse {
 async fn start_recording(
     State(state): State<Arc<AppState>>,
     Json(payload): Json<StartRequest>,
-) -> Result<Json<StartResponse>, StatusCode> {
+) -> ApiResponse<StartResponse> {
     // Update token if provided
     if let Some(token) = &payload.token {
         let mut config = state.config.lock().unwrap();
@@ -106,32 +316,44 @@ async fn start_recording(
     }
 
     // Get config for request
-    let (api_url, api_token) = {
-        let config = state.config.lock().unwrap();
-        (config.api_url.clone(), config.api_token.clone())
+    let api_url = state.config.lock().unwrap().api_url.clone();
+    let token = match state.valid_token().await {
+        Ok(token) => token,
+        Err(e) => {
+            eprintln!("Failed to authenticate with backend: {}", e);
+            return ApiResponse::Failure("Failed to authenticate with backend".to_string());
+        }
     };
 
     // 1. Call Backend to Init
     let client = reqwest::Client::new();
     let url = format!("{}/recordings/init", api_url);
-    let res = client.post(&url)
-        .header("Authorization", format!("Bearer {}", api_token))
+    let res = match client.post(&url)
+        .header("Authorization", format!("Bearer {}", token))
         .query(&[("name", &payload.name)])
         .send()
         .await
-        .map_err(|e| {
+    {
+        Ok(res) => res,
+        Err(e) => {
             eprintln!("Failed to init recording: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-        
+            return ApiResponse::Failure(format!("Failed to reach backend: {}", e));
+        }
+    };
+
     if !res.status().is_success() {
         eprintln!("Backend returned error: {}", res.status());
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        return ApiResponse::Failure(format!("Backend returned error: {}", res.status()));
     }
-    
-    let json: serde_json::Value = res.json().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let recording_id = json["id"].as_i64().ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
-    
+
+    let json: serde_json::Value = match res.json().await {
+        Ok(json) => json,
+        Err(e) => return ApiResponse::Failure(format!("Backend returned an invalid response: {}", e)),
+    };
+    let Some(recording_id) = json["id"].as_i64() else {
+        return ApiResponse::Failure("Backend response was missing a recording id".to_string());
+    };
+
     // 2. Update State
     {
         let mut status = state.status.lock().unwrap();
@@ -140,31 +362,36 @@ async fn start_recording(
         *id = Some(recording_id);
         let mut seq = state.current_sequence.lock().unwrap();
         *seq = 1;
-        
+
         // Reset timing
         let mut start_time = state.recording_start_time.lock().unwrap();
         *start_time = Some(SystemTime::now());
         let mut acc = state.accumulated_duration.lock().unwrap();
         *acc = std::time::Duration::new(0, 0);
     }
-    
+    publish_status(&state);
+    state.recordings_started_total.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
     // 2. Send Start Command to Audio Thread
-    state.audio_command_tx.send(AudioCommand::Start(recording_id)).unwrap();
-    
+    if let Err(e) = state.audio_command_tx.send(AudioCommand::Start(recording_id)) {
+        eprintln!("Audio command channel is dead: {}", e);
+        return ApiResponse::Fatal("Audio thread is not responding".to_string());
+    }
+
     // Notify Backend of Status
-    let config_clone = state.config.lock().unwrap().clone();
+    let state_clone = state.clone();
     tokio::spawn(async move {
-        if let Err(e) = uploader::update_client_status(recording_id, "RECORDING", &config_clone).await {
+        if let Err(e) = uploader::update_client_status(recording_id, "RECORDING", &state_clone).await {
             eprintln!("Failed to update client status: {}", e);
         }
     });
-    
+
     notifications::show_notification("Recording Started", "Nojoin is now recording.");
 
-    Ok(Json(StartResponse {
+    ApiResponse::Success(StartResponse {
         id: recording_id,
         message: "Recording started".to_string(),
-    }))
+    })
 }
 
 #[derive(serde::Deserialize)]
@@ -175,7 +402,7 @@ struct StopRequest {
 async fn stop_recording(
     State(state): State<Arc<AppState>>,
     Json(payload): Json<Option<StopRequest>>,
-) -> Result<Json<String>, StatusCode> {
+) -> ApiResponse<String> {
     // Update token if provided
     if let Some(req) = payload {
         if let Some(token) = req.token {
@@ -189,36 +416,41 @@ async fn stop_recording(
     {
         let mut status = state.status.lock().unwrap();
         *status = AppStatus::Uploading;
-        
+
         // Reset timing
         let mut start_time = state.recording_start_time.lock().unwrap();
         *start_time = None;
         let mut acc = state.accumulated_duration.lock().unwrap();
         *acc = std::time::Duration::new(0, 0);
-        
+
         // Do NOT clear current_recording_id here. Audio thread needs it.
     }
-    state.audio_command_tx.send(AudioCommand::Stop).unwrap();
-    
+    publish_status(&state);
+    state.recordings_stopped_total.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    if let Err(e) = state.audio_command_tx.send(AudioCommand::Stop) {
+        eprintln!("Audio command channel is dead: {}", e);
+        return ApiResponse::Fatal("Audio thread is not responding".to_string());
+    }
+
     if let Some(id) = recording_id {
-        let config_clone = state.config.lock().unwrap().clone();
+        let state_clone = state.clone();
         tokio::spawn(async move {
-            if let Err(e) = uploader::update_client_status(id, "UPLOADING", &config_clone).await {
+            if let Err(e) = uploader::update_client_status(id, "UPLOADING", &state_clone).await {
                 eprintln!("Failed to update client status: {}", e);
             }
         });
     }
 
     notifications::show_notification("Recording Stopped", "Processing audio...");
-    Ok(Json("Stopped".to_string()))
+    ApiResponse::Success("Stopped".to_string())
 }
 
-async fn pause_recording(State(state): State<Arc<AppState>>) -> Result<Json<String>, StatusCode> {
+async fn pause_recording(State(state): State<Arc<AppState>>) -> ApiResponse<String> {
     let recording_id = *state.current_recording_id.lock().unwrap();
     {
         let mut status = state.status.lock().unwrap();
         *status = AppStatus::Paused;
-        
+
         // Accumulate time
         let mut start_time = state.recording_start_time.lock().unwrap();
         if let Some(s) = *start_time {
@@ -229,51 +461,115 @@ async fn pause_recording(State(state): State<Arc<AppState>>) -> Result<Json<Stri
         }
         *start_time = None;
     }
-    state.audio_command_tx.send(AudioCommand::Pause).unwrap();
-    
+    publish_status(&state);
+    if let Err(e) = state.audio_command_tx.send(AudioCommand::Pause) {
+        eprintln!("Audio command channel is dead: {}", e);
+        return ApiResponse::Fatal("Audio thread is not responding".to_string());
+    }
+
     if let Some(id) = recording_id {
-        let config_clone = state.config.lock().unwrap().clone();
+        let state_clone = state.clone();
         tokio::spawn(async move {
-            if let Err(e) = uploader::update_client_status(id, "PAUSED", &config_clone).await {
+            if let Err(e) = uploader::update_client_status(id, "PAUSED", &state_clone).await {
                 eprintln!("Failed to update client status: {}", e);
             }
         });
     }
-    
+
     notifications::show_notification("Recording Paused", "Recording paused.");
-    Ok(Json("Paused".to_string()))
+    ApiResponse::Success("Paused".to_string())
 }
 
-async fn resume_recording(State(state): State<Arc<AppState>>) -> Result<Json<String>, StatusCode> {
+async fn resume_recording(State(state): State<Arc<AppState>>) -> ApiResponse<String> {
     let recording_id = *state.current_recording_id.lock().unwrap();
     {
         let mut status = state.status.lock().unwrap();
         *status = AppStatus::Recording;
         let mut seq = state.current_sequence.lock().unwrap();
         *seq += 1;
-        
+
         // Resume timing
         let mut start_time = state.recording_start_time.lock().unwrap();
         *start_time = Some(SystemTime::now());
     }
-    state.audio_command_tx.send(AudioCommand::Resume).unwrap();
-    
+    publish_status(&state);
+    if let Err(e) = state.audio_command_tx.send(AudioCommand::Resume) {
+        eprintln!("Audio command channel is dead: {}", e);
+        return ApiResponse::Fatal("Audio thread is not responding".to_string());
+    }
+
     if let Some(id) = recording_id {
-        let config_clone = state.config.lock().unwrap().clone();
+        let state_clone = state.clone();
         tokio::spawn(async move {
-            if let Err(e) = uploader::update_client_status(id, "RECORDING", &config_clone).await {
+            if let Err(e) = uploader::update_client_status(id, "RECORDING", &state_clone).await {
                 eprintln!("Failed to update client status: {}", e);
             }
         });
     }
 
     notifications::show_notification("Recording Resumed", "Recording resumed.");
-    Ok(Json("Resumed".to_string()))
+    ApiResponse::Success("Resumed".to_string())
 }
 
-async fn get_config(State(state): State<Arc<AppState>>) -> Json<Config> {
-    let config = state.config.lock().unwrap().clone();
-    Json(config)
+/// Everything about `Config` that's safe to hand back to a caller authenticated only
+/// with a scoped token (see `require_control_token`), which is deliberately lower-trust
+/// and handed out to the web UI. Excludes `api_token`/`access_token`/`refresh_token`
+/// (permanent backend credentials — `uploader.rs` sends `api_token` as a bearer token
+/// to the real backend the same way an OAuth access token would) and `control_secret`
+/// (mints scoped tokens on its own), so holding a short-lived scoped token can never be
+/// leveraged into any of them.
+#[derive(serde::Serialize)]
+struct ConfigResponse {
+    api_url: String,
+    scoped_token_ttl_secs: u64,
+    install_id: String,
+    update_manifest_url: String,
+    channel: crate::config::ReleaseChannel,
+    check_interval_hours: u64,
+    update_check_timeout_secs: u64,
+    tls_cert_path: Option<String>,
+    tls_key_path: Option<String>,
+    segment_codec: crate::config::SegmentCodec,
+    input_device_name: Option<String>,
+    output_device_name: Option<String>,
+    input_device_index: Option<usize>,
+    output_device_index: Option<usize>,
+    input_buffer_frames: Option<u32>,
+    output_buffer_frames: Option<u32>,
+    input_sample_rate: Option<u32>,
+    output_sample_rate: Option<u32>,
+    recording_mode: crate::config::RecordingMode,
+}
+
+impl From<&Config> for ConfigResponse {
+    fn from(config: &Config) -> Self {
+        ConfigResponse {
+            api_url: config.api_url.clone(),
+            scoped_token_ttl_secs: config.scoped_token_ttl_secs,
+            install_id: config.install_id.clone(),
+            update_manifest_url: config.update_manifest_url.clone(),
+            channel: config.channel.clone(),
+            check_interval_hours: config.check_interval_hours,
+            update_check_timeout_secs: config.update_check_timeout_secs,
+            tls_cert_path: config.tls_cert_path.clone(),
+            tls_key_path: config.tls_key_path.clone(),
+            segment_codec: config.segment_codec.clone(),
+            input_device_name: config.input_device_name.clone(),
+            output_device_name: config.output_device_name.clone(),
+            input_device_index: config.input_device_index,
+            output_device_index: config.output_device_index,
+            input_buffer_frames: config.input_buffer_frames,
+            output_buffer_frames: config.output_buffer_frames,
+            input_sample_rate: config.input_sample_rate,
+            output_sample_rate: config.output_sample_rate,
+            recording_mode: config.recording_mode.clone(),
+        }
+    }
+}
+
+async fn get_config(State(state): State<Arc<AppState>>) -> ApiResponse<ConfigResponse> {
+    let config = state.config.lock().unwrap();
+    ApiResponse::Success(ConfigResponse::from(&*config))
 }
 
 #[derive(serde::Serialize)]
@@ -290,14 +586,14 @@ struct DevicesResponse {
     selected_output: Option<String>,
 }
 
-async fn get_devices(State(state): State<Arc<AppState>>) -> Json<DevicesResponse> {
+async fn get_devices(State(state): State<Arc<AppState>>) -> ApiResponse<DevicesResponse> {
     let host = cpal::default_host();
-    
+
     let default_input_name = host.default_input_device()
         .and_then(|d| d.name().ok());
     let default_output_name = host.default_output_device()
         .and_then(|d| d.name().ok());
-    
+
     let input_devices: Vec<AudioDevice> = host.input_devices()
         .map(|devices| {
             devices.filter_map(|d| {
@@ -308,7 +604,7 @@ async fn get_devices(State(state): State<Arc<AppState>>) -> Json<DevicesResponse
             }).collect()
         })
         .unwrap_or_default();
-    
+
     let output_devices: Vec<AudioDevice> = host.output_devices()
         .map(|devices| {
             devices.filter_map(|d| {
@@ -319,10 +615,10 @@ async fn get_devices(State(state): State<Arc<AppState>>) -> Json<DevicesResponse
             }).collect()
         })
         .unwrap_or_default();
-    
+
     let config = state.config.lock().unwrap();
-    
-    Json(DevicesResponse {
+
+    ApiResponse::Success(DevicesResponse {
         input_devices,
         output_devices,
         selected_input: config.input_device_name.clone(),
@@ -341,9 +637,9 @@ struct ConfigUpdate {
 async fn update_config(
     State(state): State<Arc<AppState>>,
     Json(payload): Json<ConfigUpdate>,
-) -> Result<Json<Config>, StatusCode> {
+) -> ApiResponse<ConfigResponse> {
     let mut config = state.config.lock().unwrap();
-    
+
     if let Some(url) = payload.api_url {
         config.api_url = url;
     }
@@ -356,11 +652,11 @@ async fn update_config(
     if payload.output_device_name.is_some() {
         config.output_device_name = payload.output_device_name;
     }
-    
+
     if let Err(e) = config.save() {
         eprintln!("Failed to save config: {}", e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        return ApiResponse::Failure(format!("Failed to save config: {}", e));
     }
-    
-    Ok(Json(config.clone()))
+
+    ApiResponse::Success(ConfigResponse::from(&*config))
 }
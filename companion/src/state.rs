@@ -1,18 +1,49 @@
+use std::collections::HashMap;
 use std::sync::Mutex;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
 use serde::{Serialize, Deserialize};
 use crossbeam_channel::Sender;
 use crate::config::Config;
 
+/// How far ahead of the recorded expiry to refresh, so a request in flight doesn't
+/// race a token that expires mid-call.
+const TOKEN_REFRESH_SKEW: Duration = Duration::from_secs(60);
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum AppStatus {
     Idle,
     Recording,
     Paused,
     Uploading,
+    // Transient: a capture device dropped mid-recording and the audio loop is
+    // re-acquiring it and starting a fresh segment. Clears back to `Recording`
+    // once the replacement stream is up.
+    DeviceError,
     Error(String),
 }
 
+/// A shared wall-clock/monotonic reference latched once when a recording (re)starts,
+/// so both the mic and system-audio streams can timestamp their chunks against the
+/// same origin even though they're captured by independent device callbacks.
+#[derive(Debug, Clone, Copy)]
+pub struct StreamOrigin {
+    pub instant: Instant,
+    pub unix_time: SystemTime,
+}
+
+/// Per-stream capture-time mapping attached to every uploaded segment. Lets the
+/// backend line up the mic and system-audio streams on one sample-accurate
+/// timeline instead of trusting the order chunks happen to arrive in.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChunkHeader {
+    pub stream: &'static str,
+    pub first_sample_index: u64,
+    pub capture_unix_nanos: u64,
+    pub monotonic_nanos: u64,
+    pub sample_rate: u32,
+}
+
 pub struct AppState {
     pub status: Mutex<AppStatus>,
     pub current_recording_id: Mutex<Option<i64>>,
@@ -24,6 +55,32 @@ pub struct AppState {
     // Audio levels (0-100 scaled, stored as u32 for atomic access)
     pub input_level: AtomicU32,
     pub output_level: AtomicU32,
+    // Telemetry counters exposed via /metrics, kept lock-free
+    pub recordings_started_total: AtomicU64,
+    pub recordings_stopped_total: AtomicU64,
+    pub upload_successes_total: AtomicU64,
+    pub upload_failures_total: AtomicU64,
+    // Single-flight guard so concurrently spawned status updates don't all refresh
+    // the OAuth token at once; held only while a refresh is actually in progress.
+    pub refresh_lock: tokio::sync::Mutex<()>,
+    // Short-lived tokens minted by `/auth/scoped`, keyed by the token string itself.
+    // Not persisted; revoked on expiry by a background sweep.
+    pub scoped_tokens: Mutex<HashMap<String, Instant>>,
+    // Pushed to `/ws` subscribers as pre-serialized JSON frames on every status
+    // transition and periodic level snapshot, so handlers never need to know who
+    // (if anyone) is listening.
+    pub ws_tx: tokio::sync::broadcast::Sender<String>,
+    // Fixed instant established at process start; `StreamOrigin::instant` offsets
+    // against this are what travel in `ChunkHeader::monotonic_nanos`.
+    pub process_epoch: Instant,
+    // Latched on `AudioCommand::Start`/`Resume` so both audio callbacks timestamp
+    // against the same wall-clock/monotonic pair; `None` before the first segment.
+    pub stream_origin: Mutex<Option<StreamOrigin>>,
+    // Cumulative sample counts per stream since the current recording's `Start`,
+    // reset on `Start` but left running across a `Pause`/`Resume` so the gap is
+    // visible in the next segment's `first_sample_index`.
+    pub mic_samples_total: AtomicU64,
+    pub sys_samples_total: AtomicU64,
 }
 
 impl AppState {
@@ -45,6 +102,90 @@ impl AppState {
     pub fn get_output_level(&self) -> u32 {
         self.output_level.load(Ordering::Relaxed)
     }
+
+    /// Returns a currently-valid bearer token for backend calls, transparently
+    /// refreshing the OAuth access token first if it's within `TOKEN_REFRESH_SKEW`
+    /// of expiring. Falls back to the legacy static `api_token` when no OAuth
+    /// token is configured. Refreshing is single-flighted through `refresh_lock`
+    /// so two tasks racing to refresh don't both hit `/auth/refresh`.
+    pub async fn valid_token(&self) -> anyhow::Result<String> {
+        if !self.access_token_needs_refresh() {
+            let config = self.config.lock().unwrap();
+            return Ok(config.access_token.clone().unwrap_or_else(|| config.api_token.clone()));
+        }
+
+        // Only one task actually talks to /auth/refresh at a time; whoever loses
+        // the race just reads back what the winner already persisted.
+        let _guard = self.refresh_lock.lock().await;
+
+        if !self.access_token_needs_refresh() {
+            let config = self.config.lock().unwrap();
+            return Ok(config.access_token.clone().unwrap_or_else(|| config.api_token.clone()));
+        }
+
+        let (api_url, refresh_token, fallback) = {
+            let config = self.config.lock().unwrap();
+            (
+                config.api_url.clone(),
+                config.refresh_token.clone(),
+                config.access_token.clone().unwrap_or_else(|| config.api_token.clone()),
+            )
+        };
+
+        let Some(refresh_token) = refresh_token else {
+            // No refresh token on file; hand back what we have and let the caller
+            // discover it's stale from the backend's response.
+            return Ok(fallback);
+        };
+
+        let refreshed = refresh_access_token(&api_url, &refresh_token).await?;
+
+        {
+            let mut config = self.config.lock().unwrap();
+            config.access_token = Some(refreshed.access_token.clone());
+            config.refresh_token = Some(refreshed.refresh_token);
+            config.expires_at = Some(SystemTime::now() + Duration::from_secs(refreshed.expires_in));
+            if let Err(e) = config.save() {
+                eprintln!("Failed to persist refreshed token: {}", e);
+            }
+        }
+
+        Ok(refreshed.access_token)
+    }
+
+    fn access_token_needs_refresh(&self) -> bool {
+        let config = self.config.lock().unwrap();
+        if config.access_token.is_none() {
+            return false;
+        }
+        match config.expires_at {
+            Some(exp) => exp
+                .duration_since(SystemTime::now())
+                .map(|remaining| remaining < TOKEN_REFRESH_SKEW)
+                .unwrap_or(true),
+            None => false,
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct RefreshResponse {
+    access_token: String,
+    refresh_token: String,
+    expires_in: u64,
+}
+
+async fn refresh_access_token(api_url: &str, refresh_token: &str) -> anyhow::Result<RefreshResponse> {
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("{}/auth/refresh", api_url))
+        .json(&serde_json::json!({ "refresh_token": refresh_token }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<RefreshResponse>()
+        .await?;
+    Ok(response)
 }
 
 
@@ -1,134 +1,492 @@
-use log::{info, warn};
-use crate::notifications;
-
-const GITHUB_REPO: &str = "Valtora/Nojoin";
-const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
-
-#[derive(serde::Deserialize)]
-struct GitHubRelease {
-    tag_name: String,
-    html_url: String,
-}
-
-fn parse_version(version: &str) -> Option<(u32, u32, u32)> {
-    let v = version.trim_start_matches('v');
-    let parts: Vec<&str> = v.split('.').collect();
-    if parts.len() >= 3 {
-        let major = parts[0].parse().ok()?;
-        let minor = parts[1].parse().ok()?;
-        let patch = parts[2].parse().ok()?;
-        Some((major, minor, patch))
-    } else if parts.len() == 2 {
-        let major = parts[0].parse().ok()?;
-        let minor = parts[1].parse().ok()?;
-        Some((major, minor, 0))
-    } else {
-        None
-    }
-}
-
-fn is_newer_version(current: &str, latest: &str) -> bool {
-    match (parse_version(current), parse_version(latest)) {
-        (Some((c_major, c_minor, c_patch)), Some((l_major, l_minor, l_patch))) => {
-            if l_major > c_major {
-                return true;
-            }
-            if l_major == c_major && l_minor > c_minor {
-                return true;
-            }
-            if l_major == c_major && l_minor == c_minor && l_patch > c_patch {
-                return true;
-            }
-            false
-        }
-        _ => false,
-    }
-}
-
-async fn fetch_latest_release() -> Option<GitHubRelease> {
-    let client = reqwest::Client::builder()
-        .user_agent("NojoinCompanion")
-        .timeout(std::time::Duration::from_secs(10))
-        .build()
-        .ok()?;
-    
-    let url = format!("https://api.github.com/repos/{}/releases/latest", GITHUB_REPO);
-    
-    match client.get(&url).send().await {
-        Ok(response) => {
-            if response.status().is_success() {
-                response.json::<GitHubRelease>().await.ok()
-            } else {
-                warn!("GitHub API returned status: {}", response.status());
-                None
-            }
-        }
-        Err(e) => {
-            warn!("Failed to fetch latest release: {}", e);
-            None
-        }
-    }
-}
-
-pub async fn check_for_updates() {
-    info!("Checking for updates (current version: {})...", CURRENT_VERSION);
-    
-    if let Some(release) = fetch_latest_release().await {
-        let latest_version = release.tag_name.trim_start_matches('v');
-        
-        if is_newer_version(CURRENT_VERSION, latest_version) {
-            info!("New version available: {} (current: {})", latest_version, CURRENT_VERSION);
-            notifications::show_notification(
-                "Update Available",
-                &format!("Nojoin Companion {} is available. Click 'Check for Updates' in the menu to download.", latest_version)
-            );
-        } else {
-            info!("Already on the latest version ({})", CURRENT_VERSION);
-        }
-    }
-}
-
-pub async fn check_for_updates_interactive() {
-    info!("Manual update check triggered...");
-    
-    if let Some(release) = fetch_latest_release().await {
-        let latest_version = release.tag_name.trim_start_matches('v');
-        
-        if is_newer_version(CURRENT_VERSION, latest_version) {
-            info!("New version available: {}", latest_version);
-            notifications::show_notification(
-                "Update Available",
-                &format!("Nojoin Companion {} is available!", latest_version)
-            );
-            // Open the releases page
-            let _ = open::that(&release.html_url);
-        } else {
-            notifications::show_notification(
-                "No Updates Available",
-                &format!("You are on the latest version ({}).", CURRENT_VERSION)
-            );
-        }
-    } else {
-        notifications::show_notification(
-            "Update Check Failed",
-            "Could not check for updates. Please try again later."
-        );
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_version_comparison() {
-        assert!(is_newer_version("0.1.0", "0.1.1"));
-        assert!(is_newer_version("0.1.0", "0.2.0"));
-        assert!(is_newer_version("0.1.0", "1.0.0"));
-        assert!(!is_newer_version("0.1.0", "0.1.0"));
-        assert!(!is_newer_version("0.2.0", "0.1.0"));
-        assert!(is_newer_version("0.1.0", "v0.2.0"));
-    }
-}
-
-
+use log::{info, warn};
+use crate::config::{Config, ReleaseChannel};
+use crate::notifications;
+use std::collections::HashMap;
+
+const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Update manifest served from `Config::update_manifest_url`. Supersedes the old
+/// "read `tag_name` off `/releases/latest`" check with fields rich enough to
+/// support phased rollouts, server-driven rollback, and a beta channel.
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct Manifest {
+    pub version: String,
+    #[serde(default)]
+    pub notes: String,
+    /// Percentage (0-100) of installs that should be offered this version.
+    /// Installs are bucketed by `Config::install_id` so the same install always
+    /// lands in the same bucket; ramping this up over time offers the update to
+    /// more installs without the server needing to track who's seen it.
+    #[serde(default = "default_rollout")]
+    pub rollout: u8,
+    /// If set, installs on an older version than this are never offered the
+    /// update, e.g. because it depends on a migration shipped in between.
+    #[serde(default)]
+    pub minimum_version: Option<String>,
+    /// Optional per-platform/arch download URL override, keyed e.g. by
+    /// "windows-x86_64". Unused by this client today but accepted so the same
+    /// manifest can serve other Nojoin clients.
+    #[serde(default)]
+    pub target: Option<HashMap<String, String>>,
+    #[serde(default)]
+    pub html_url: Option<String>,
+    /// Pre-release builds, newest and oldest in any order. Only considered on
+    /// `Config::channel == Beta`; `Stable` installs never look at this list.
+    #[serde(default)]
+    pub prereleases: Vec<PrereleaseBuild>,
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct PrereleaseBuild {
+    pub version: String,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub html_url: Option<String>,
+}
+
+fn default_rollout() -> u8 {
+    100
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstallDecision {
+    Install,
+    Skip,
+}
+
+/// The concrete build this install was offered, after channel selection.
+/// Distinct from `Manifest` since on `Beta` it may be a `PrereleaseBuild`
+/// rather than `Manifest::version` itself.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct Release {
+    pub version: String,
+    pub notes: String,
+    pub html_url: Option<String>,
+}
+
+fn parse_version(version: &str) -> Option<semver::Version> {
+    let v = version.trim_start_matches('v');
+    semver::Version::parse(v).or_else(|_| semver::Version::parse(&format!("{}.0", v))).ok()
+}
+
+fn is_newer_version(current: &str, latest: &str) -> bool {
+    match (parse_version(current), parse_version(latest)) {
+        (Some(c), Some(l)) => l > c,
+        _ => false,
+    }
+}
+
+/// Picks the release `channel` should consider out of `manifest`. `Stable`
+/// only ever looks at `manifest.version`, skipped entirely if it carries a
+/// pre-release tag (e.g. a manifest mid-rollout of a beta build). `Beta`
+/// considers `manifest.version` plus every `prereleases` entry and picks
+/// whichever has the highest semver precedence, so a published beta always
+/// wins over an older stable build but never over a newer stable one.
+fn select_release(manifest: &Manifest, channel: &ReleaseChannel) -> Option<Release> {
+    match channel {
+        ReleaseChannel::Stable => {
+            let parsed = parse_version(&manifest.version)?;
+            if !parsed.pre.is_empty() {
+                return None;
+            }
+            Some(Release {
+                version: manifest.version.clone(),
+                notes: manifest.notes.clone(),
+                html_url: manifest.html_url.clone(),
+            })
+        }
+        ReleaseChannel::Beta => {
+            let stable = parse_version(&manifest.version).map(|v| {
+                (v, Release {
+                    version: manifest.version.clone(),
+                    notes: manifest.notes.clone(),
+                    html_url: manifest.html_url.clone(),
+                })
+            });
+            let prereleases = manifest.prereleases.iter().filter_map(|build| {
+                parse_version(&build.version).map(|v| {
+                    (v, Release {
+                        version: build.version.clone(),
+                        notes: build.notes.clone(),
+                        html_url: build.html_url.clone(),
+                    })
+                })
+            });
+            stable
+                .into_iter()
+                .chain(prereleases)
+                .max_by(|(a, _), (b, _)| a.cmp(b))
+                .map(|(_, release)| release)
+        }
+    }
+}
+
+/// Hashes an install ID into a stable 0-99 bucket. Deliberately not
+/// `std::collections::hash_map::DefaultHasher` (its algorithm is unspecified
+/// and may change between Rust versions) — FNV-1a so the same install lands in
+/// the same bucket forever.
+fn install_bucket(install_id: &str) -> u8 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in install_id.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    (hash % 100) as u8
+}
+
+fn default_should_install(
+    current: &str,
+    candidate: &Release,
+    manifest: &Manifest,
+    bucket: u8,
+) -> InstallDecision {
+    if candidate.version == current {
+        return InstallDecision::Skip;
+    }
+
+    if let Some(minimum) = &manifest.minimum_version {
+        if is_newer_version(current, minimum) {
+            // Current install is older than what this update requires; wait for
+            // an intermediate release to land first.
+            return InstallDecision::Skip;
+        }
+    }
+
+    if bucket >= manifest.rollout {
+        return InstallDecision::Skip;
+    }
+
+    // No lower bound on `candidate.version` vs `current` here: a server
+    // advertising an older version than `CURRENT_VERSION` is an intentional
+    // rollback, and should be honored rather than filtered out.
+    InstallDecision::Install
+}
+
+/// Fetches `Config::update_manifest_url`, resolves it to a release for
+/// `Config::channel`, and decides whether to install it via a pluggable
+/// policy, mirroring the custom-version-comparator builder pattern Tauri's own
+/// updater plugin exposes: construct with sane rollout/rollback defaults, then
+/// override `should_install` for callers that need something different.
+pub struct UpdateChecker {
+    manifest_url: String,
+    channel: ReleaseChannel,
+    timeout_secs: u64,
+    should_install: Box<dyn Fn(&str, &Release, &Manifest) -> InstallDecision + Send + Sync>,
+}
+
+impl UpdateChecker {
+    pub fn new(manifest_url: String, install_id: &str, channel: ReleaseChannel, timeout_secs: u64) -> Self {
+        let bucket = install_bucket(install_id);
+        Self {
+            manifest_url,
+            channel,
+            timeout_secs,
+            should_install: Box::new(move |current, candidate, manifest| {
+                default_should_install(current, candidate, manifest, bucket)
+            }),
+        }
+    }
+
+    pub fn should_install(
+        mut self,
+        f: Box<dyn Fn(&str, &Release, &Manifest) -> InstallDecision + Send + Sync>,
+    ) -> Self {
+        self.should_install = f;
+        self
+    }
+
+    fn decide(&self, candidate: &Release, manifest: &Manifest) -> InstallDecision {
+        (self.should_install)(CURRENT_VERSION, candidate, manifest)
+    }
+
+    async fn fetch_manifest(&self) -> Option<Manifest> {
+        let client = reqwest::Client::builder()
+            .user_agent("NojoinCompanion")
+            .timeout(std::time::Duration::from_secs(self.timeout_secs))
+            .build()
+            .ok()?;
+
+        match client.get(&self.manifest_url).send().await {
+            Ok(response) => {
+                if response.status().is_success() {
+                    response.json::<Manifest>().await.ok()
+                } else {
+                    warn!("Update manifest endpoint returned status: {}", response.status());
+                    None
+                }
+            }
+            Err(e) => {
+                warn!("Failed to fetch update manifest: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Fetches the manifest, resolves a release for the configured channel,
+    /// and returns it only if `should_install` says this install should be
+    /// offered it.
+    pub async fn check(&self) -> Option<Release> {
+        let manifest = self.fetch_manifest().await?;
+        let candidate = select_release(&manifest, &self.channel)?;
+        match self.decide(&candidate, &manifest) {
+            InstallDecision::Install => Some(candidate),
+            InstallDecision::Skip => None,
+        }
+    }
+}
+
+fn checker_for(config: &Config) -> UpdateChecker {
+    UpdateChecker::new(
+        config.update_manifest_url.clone(),
+        &config.install_id,
+        config.channel.clone(),
+        config.update_check_timeout_secs,
+    )
+}
+
+const CHECK_STATE_PATH: &str = "update_check_state.json";
+
+/// Result of the last background update check, persisted beside `config.json`
+/// so `check_for_updates` can skip the network entirely on a cold start that
+/// happens well within `Config::check_interval_hours` of the last one.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
+struct CheckState {
+    last_check_unix_secs: Option<u64>,
+    last_seen_release: Option<Release>,
+}
+
+fn load_check_state() -> CheckState {
+    let path = std::path::Path::new(CHECK_STATE_PATH);
+    if !path.exists() {
+        return CheckState::default();
+    }
+    match std::fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
+        Err(e) => {
+            eprintln!("Failed to read update check state: {}", e);
+            CheckState::default()
+        }
+    }
+}
+
+fn save_check_state(state: &CheckState) {
+    match serde_json::to_string_pretty(state) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(CHECK_STATE_PATH, json) {
+                eprintln!("Failed to persist update check state: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to serialize update check state: {}", e),
+    }
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn notify_release_available(release: &Release) {
+    notifications::show_notification(
+        "Update Available",
+        &format!("Nojoin Companion {} is available. Click 'Check for Updates' in the menu to download.", release.version)
+    );
+}
+
+/// Runs the background update check, throttled to at most once per
+/// `Config::check_interval_hours`. Within the interval, re-shows the
+/// previously discovered update (if any) from the persisted state file
+/// instead of hitting the network again — a manual "Check for Updates" from
+/// the tray menu (`check_for_updates_interactive`) always bypasses this.
+pub async fn check_for_updates(config: &Config) {
+    let mut state = load_check_state();
+    let now = unix_now();
+    let interval_secs = config.check_interval_hours.saturating_mul(3600);
+
+    if let Some(last_check) = state.last_check_unix_secs {
+        if now.saturating_sub(last_check) < interval_secs {
+            match &state.last_seen_release {
+                Some(release) => {
+                    info!("Using cached update check result: {} available", release.version);
+                    notify_release_available(release);
+                }
+                None => {
+                    info!(
+                        "Skipping update check; last check was {}s ago (interval {}s)",
+                        now.saturating_sub(last_check),
+                        interval_secs
+                    );
+                }
+            }
+            return;
+        }
+    }
+
+    info!("Checking for updates (current version: {})...", CURRENT_VERSION);
+    let checker = checker_for(config);
+    match checker.check().await {
+        Some(release) => {
+            info!("New version available: {} (current: {})", release.version, CURRENT_VERSION);
+            notify_release_available(&release);
+            state.last_seen_release = Some(release);
+        }
+        None => {
+            info!("Already on the latest version ({})", CURRENT_VERSION);
+            state.last_seen_release = None;
+        }
+    }
+    state.last_check_unix_secs = Some(now);
+    save_check_state(&state);
+}
+
+pub async fn check_for_updates_interactive(config: &Config) {
+    info!("Manual update check triggered...");
+
+    let checker = checker_for(config);
+    match checker.fetch_manifest().await {
+        Some(manifest) => match select_release(&manifest, &checker.channel) {
+            Some(candidate) => match checker.decide(&candidate, &manifest) {
+                InstallDecision::Install => {
+                    info!("New version available: {}", candidate.version);
+                    notifications::show_notification(
+                        "Update Available",
+                        &format!("Nojoin Companion {} is available!", candidate.version)
+                    );
+                    if let Some(url) = &candidate.html_url {
+                        let _ = open::that(url);
+                    }
+                }
+                InstallDecision::Skip => {
+                    notifications::show_notification(
+                        "No Updates Available",
+                        &format!("You are on the latest version ({}).", CURRENT_VERSION)
+                    );
+                }
+            },
+            None => {
+                notifications::show_notification(
+                    "No Updates Available",
+                    &format!("You are on the latest version ({}).", CURRENT_VERSION)
+                );
+            }
+        },
+        None => {
+            notifications::show_notification(
+                "Update Check Failed",
+                "Could not check for updates. Please try again later."
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_comparison() {
+        assert!(is_newer_version("0.1.0", "0.1.1"));
+        assert!(is_newer_version("0.1.0", "0.2.0"));
+        assert!(is_newer_version("0.1.0", "1.0.0"));
+        assert!(!is_newer_version("0.1.0", "0.1.0"));
+        assert!(!is_newer_version("0.2.0", "0.1.0"));
+        assert!(is_newer_version("0.1.0", "v0.2.0"));
+    }
+
+    #[test]
+    fn test_version_comparison_prerelease_precedence() {
+        // A pre-release sorts below its corresponding release.
+        assert!(is_newer_version("1.2.0-rc.1", "1.2.0"));
+        assert!(!is_newer_version("1.2.0", "1.2.0-rc.1"));
+        // Pre-release identifiers compare in their own right.
+        assert!(is_newer_version("1.2.0-alpha", "1.2.0-beta"));
+        assert!(!is_newer_version("1.2.0-beta", "1.2.0-alpha"));
+        assert!(is_newer_version("1.2.0-rc.1", "1.2.0-rc.2"));
+    }
+
+    #[test]
+    fn test_install_bucket_is_stable() {
+        let id = "11111111-1111-1111-1111-111111111111";
+        assert_eq!(install_bucket(id), install_bucket(id));
+    }
+
+    fn manifest(version: &str, rollout: u8, minimum_version: Option<&str>) -> Manifest {
+        Manifest {
+            version: version.to_string(),
+            notes: String::new(),
+            rollout,
+            minimum_version: minimum_version.map(|v| v.to_string()),
+            target: None,
+            html_url: None,
+            prereleases: Vec::new(),
+        }
+    }
+
+    fn release(version: &str) -> Release {
+        Release { version: version.to_string(), notes: String::new(), html_url: None }
+    }
+
+    #[test]
+    fn test_should_install_honors_rollout_bucket() {
+        let m = manifest("1.1.0", 0, None);
+        assert_eq!(default_should_install("1.0.0", &release("1.1.0"), &m, 50), InstallDecision::Skip);
+
+        let m = manifest("1.1.0", 100, None);
+        assert_eq!(default_should_install("1.0.0", &release("1.1.0"), &m, 50), InstallDecision::Install);
+    }
+
+    #[test]
+    fn test_should_install_honors_rollback() {
+        let m = manifest("0.9.0", 100, None);
+        assert_eq!(default_should_install("1.0.0", &release("0.9.0"), &m, 0), InstallDecision::Install);
+    }
+
+    #[test]
+    fn test_should_install_honors_minimum_version() {
+        let m = manifest("2.0.0", 100, Some("1.5.0"));
+        assert_eq!(default_should_install("1.0.0", &release("2.0.0"), &m, 0), InstallDecision::Skip);
+        assert_eq!(default_should_install("1.5.0", &release("2.0.0"), &m, 0), InstallDecision::Install);
+    }
+
+    #[test]
+    fn test_select_release_stable_skips_prerelease_version() {
+        let mut m = manifest("1.2.0-rc.1", 100, None);
+        m.prereleases.push(PrereleaseBuild {
+            version: "1.2.0-rc.2".to_string(),
+            notes: String::new(),
+            html_url: None,
+        });
+        assert!(select_release(&m, &ReleaseChannel::Stable).is_none());
+    }
+
+    #[test]
+    fn test_select_release_beta_picks_highest_precedence() {
+        let mut m = manifest("1.2.0", 100, None);
+        m.prereleases.push(PrereleaseBuild {
+            version: "1.3.0-rc.1".to_string(),
+            notes: String::new(),
+            html_url: None,
+        });
+        m.prereleases.push(PrereleaseBuild {
+            version: "1.3.0-rc.2".to_string(),
+            notes: String::new(),
+            html_url: None,
+        });
+        let selected = select_release(&m, &ReleaseChannel::Beta).unwrap();
+        assert_eq!(selected.version, "1.3.0-rc.2");
+    }
+
+    #[test]
+    fn test_select_release_beta_prefers_stable_when_newer() {
+        let mut m = manifest("2.0.0", 100, None);
+        m.prereleases.push(PrereleaseBuild {
+            version: "1.9.0-rc.1".to_string(),
+            notes: String::new(),
+            html_url: None,
+        });
+        let selected = select_release(&m, &ReleaseChannel::Beta).unwrap();
+        assert_eq!(selected.version, "2.0.0");
+    }
+}
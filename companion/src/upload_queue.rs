@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const JOURNAL_PATH: &str = "upload_queue.json";
+
+/// One temp segment file still waiting on a confirmed upload. Persisted so a crash
+/// or an extended backend outage doesn't silently lose the recording: `run_audio_loop`
+/// replays whatever's left here before it starts accepting new commands.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct QueuedSegment {
+    pub file_path: PathBuf,
+    pub recording_id: i64,
+    pub sequence: i32,
+}
+
+fn load() -> Vec<QueuedSegment> {
+    let path = Path::new(JOURNAL_PATH);
+    if !path.exists() {
+        return Vec::new();
+    }
+    match std::fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
+        Err(e) => {
+            eprintln!("Failed to read upload queue journal: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+fn save(entries: &[QueuedSegment]) {
+    match serde_json::to_string_pretty(entries) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(JOURNAL_PATH, json) {
+                eprintln!("Failed to persist upload queue journal: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to serialize upload queue journal: {}", e),
+    }
+}
+
+/// Records a segment as pending before the first upload attempt, so even a crash
+/// mid-upload leaves a journal entry pointing at the file on disk.
+pub fn enqueue(entry: QueuedSegment) {
+    let mut entries = load();
+    entries.push(entry);
+    save(&entries);
+}
+
+/// Drops the entry once its upload has actually been confirmed and the temp file deleted.
+pub fn remove(file_path: &Path) {
+    let mut entries = load();
+    entries.retain(|e| e.file_path != file_path);
+    save(&entries);
+}
+
+/// Reconciles the journal against what's actually on disk: picks up any `temp_*`
+/// segment file the journal doesn't already know about (e.g. the process died
+/// before `enqueue` ran) and drops journal entries whose file has vanished.
+pub fn reconcile_with_disk() -> Vec<QueuedSegment> {
+    let mut entries = load();
+
+    if let Ok(dir) = std::env::current_dir() {
+        if let Ok(read_dir) = std::fs::read_dir(&dir) {
+            for entry in read_dir.flatten() {
+                let path = entry.path();
+                let Some(name) = path.file_name().and_then(|n| n.to_str()) else { continue };
+                if !name.starts_with("temp_") {
+                    continue;
+                }
+                if entries.iter().any(|e| e.file_path == path) {
+                    continue;
+                }
+                if let Some((recording_id, sequence)) = parse_temp_filename(name) {
+                    entries.push(QueuedSegment { file_path: path, recording_id, sequence });
+                }
+            }
+        }
+    }
+
+    entries.retain(|e| e.file_path.exists());
+    save(&entries);
+    entries
+}
+
+/// Parses `temp_<recording_id>_<sequence>.<ext>` back into its id/sequence.
+fn parse_temp_filename(name: &str) -> Option<(i64, i32)> {
+    let stem = name.strip_prefix("temp_")?;
+    let stem = stem.split('.').next()?;
+    let mut parts = stem.splitn(2, '_');
+    let recording_id = parts.next()?.parse().ok()?;
+    let sequence = parts.next()?.parse().ok()?;
+    Some((recording_id, sequence))
+}
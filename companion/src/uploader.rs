@@ -4,27 +4,37 @@ use anyhow::Result;
 use tokio::fs::File;
 use tokio::io::AsyncReadExt;
 use crate::config::Config;
+use crate::state::{AppState, ChunkHeader};
 
-pub async fn upload_segment(recording_id: i64, sequence: i32, file_path: &Path, config: &Config) -> Result<()> {
+pub async fn upload_segment(recording_id: i64, sequence: i32, file_path: &Path, chunk_headers: &[ChunkHeader], channels: u16, config: &Config) -> Result<()> {
     let client = reqwest::Client::new();
-    
+
     // Read file manually to avoid issues with Form::file
     let mut file = File::open(file_path).await?;
     let mut contents = Vec::new();
     file.read_to_end(&mut contents).await?;
-            
-    let url = format!("{}/recordings/{}/segment?sequence={}", config.api_url, recording_id, sequence);
-    
+
+    // `channels` tells the backend whether to expect a mixed mono track or a
+    // (mic, system audio) stereo pair, without it having to sniff the WAV header.
+    let layout = if channels >= 2 { "mic,sys" } else { "mono" };
+    let url = format!("{}/recordings/{}/segment?sequence={}&channels={}", config.api_url, recording_id, sequence, channels);
+    let chunk_headers_json = serde_json::to_string(chunk_headers).unwrap_or_default();
+    // Segment filename carries the codec (wav/opus) so the backend knows how to decode it.
+    let file_name = file_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| "segment.wav".to_string());
+
     let mut attempts = 0;
     const MAX_ATTEMPTS: u32 = 5;
-    
+
     loop {
         attempts += 1;
-        
+
         // Re-create the form for each attempt because the stream is consumed
         // We need to read the file again or clone the bytes
-        let part = multipart::Part::bytes(contents.clone()).file_name("segment.wav");
-        let form = multipart::Form::new().part("file", part);
+        let part = multipart::Part::bytes(contents.clone()).file_name(file_name.clone());
+        let form = multipart::Form::new()
+            .part("file", part)
+            .text("chunk_headers", chunk_headers_json.clone())
+            .text("layout", layout);
 
         let res = client.post(&url)
             .header("Authorization", format!("Bearer {}", config.api_token))
@@ -54,6 +64,47 @@ pub async fn upload_segment(recording_id: i64, sequence: i32, file_path: &Path,
     }
 }
 
+/// Push a status transition to the backend, authenticating with whatever `state.valid_token`
+/// hands back (OAuth access token, refreshed transparently if needed, or the legacy static token).
+pub async fn update_client_status(recording_id: i64, status: &str, state: &AppState) -> Result<()> {
+    let api_url = state.config.lock().unwrap().api_url.clone();
+    let token = state.valid_token().await?;
+    let client = reqwest::Client::new();
+    let url = format!("{}/recordings/{}/status", api_url, recording_id);
+
+    let mut attempts = 0;
+    const MAX_ATTEMPTS: u32 = 5;
+
+    loop {
+        attempts += 1;
+        let res = client.put(&url)
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&serde_json::json!({ "status": status }))
+            .send()
+            .await;
+
+        match res {
+            Ok(response) => {
+                if response.status().is_success() {
+                    return Ok(());
+                } else {
+                    eprintln!("Status update failed (attempt {}/{}): {}", attempts, MAX_ATTEMPTS, response.status());
+                }
+            },
+            Err(e) => {
+                eprintln!("Status update error (attempt {}/{}): {}", attempts, MAX_ATTEMPTS, e);
+            }
+        }
+
+        if attempts >= MAX_ATTEMPTS {
+            return Err(anyhow::anyhow!("Status update failed after {} attempts", MAX_ATTEMPTS));
+        }
+
+        let wait_time = 2u64.pow(attempts);
+        tokio::time::sleep(tokio::time::Duration::from_secs(wait_time)).await;
+    }
+}
+
 pub async fn finalize_recording(recording_id: i64, config: &Config) -> Result<()> {
     let client = reqwest::Client::new();
     let url = format!("{}/recordings/{}/finalize", config.api_url, recording_id);